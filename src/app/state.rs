@@ -1,5 +1,11 @@
-use crate::models::{Status, TodoItem};
-use crate::storage::{get_default_storage_path, load_from_file, save_to_file, TodoData};
+use crate::models::{
+    dependencies, due, merge, time_tracking, tree, Due, Priority, Recurrence, Status, TimeEntry,
+    TodoItem, TreeRow,
+};
+use crate::storage::backup::Backup;
+use crate::storage::emergency::{self, Dump};
+use crate::storage::git::Revision;
+use crate::storage::{lists, MemoryStorage, Storage, TodoData};
 
 use chrono::NaiveDate;
 use ratatui::{
@@ -8,13 +14,20 @@ use ratatui::{
     widgets::ListState,
     Terminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum EditingField {
     TaskName,
     Description,
     DueDate,
+    Estimate,
+    Recurrence,
+    Priority,
     Tags,
 }
 
@@ -26,6 +39,79 @@ pub struct App {
     pub editing_task: Option<TodoItem>,
     pub current_editing_field: EditingField,
     pub new_task: Option<NewTask>,
+    pub pending_confirmation: Option<Confirmation>,
+    // Task that is picking a prerequisite while in `Mode::Linking`
+    pub linking_task: Option<Uuid>,
+    // One-off feedback shown in the footer until the next key press
+    pub status_message: Option<String>,
+    // Text typed into a popup prompt, e.g. how long to defer a task for
+    pub prompt_input: String,
+    // Selection in the time log of the selected task while in `Mode::TimeLog`
+    pub time_log_state: ListState,
+    // Entry being edited in `Mode::EditingTimeEntry`; `None` adds a new one
+    pub editing_time_entry: Option<usize>,
+    // Backups of the task file, newest first, while in `Mode::Backups`
+    pub backups: Vec<Backup>,
+    pub backup_state: ListState,
+    // Commits that changed the shown list, newest first, while in `Mode::History`
+    pub history: Vec<Revision>,
+    pub history_state: ListState,
+    // The tasks in the commit picked from the history, while in `Mode::HistoryTasks`
+    pub history_tasks: Vec<TodoItem>,
+    pub history_task_state: ListState,
+    // Set when the task file could not be loaded; nothing is written until the user recovers
+    pub read_only: bool,
+    // What went wrong loading the task file, shown until the user recovers
+    pub load_problem: Option<String>,
+    // The tasks as last read from or written to storage, the common base when merging
+    pub saved_items: Vec<TodoItem>,
+    pub storage: Box<dyn Storage>,
+    // The task list being shown, `None` for the main list
+    pub active_list: Option<String>,
+    // The lists to choose from while in `Mode::Lists`, the main list first
+    pub list_choices: Vec<Option<String>>,
+    pub list_state: ListState,
+    // Task being moved to another list while `Mode::Lists` is picking the list
+    pub moving_task: Option<Uuid>,
+    // The open tasks of all lists with the list they are in, while in `Mode::AllLists`
+    pub all_tasks: Vec<(Option<String>, TodoItem)>,
+    pub all_tasks_state: ListState,
+    // When the tasks last changed without being written yet; they are written once things calm down
+    pub last_change: Option<Instant>,
+    // Why the last write failed, shown until a write succeeds
+    pub save_error: Option<String>,
+    // Writes that failed in a row, each making the next try wait longer
+    pub failed_writes: u32,
+    // Tasks a crash left behind, with the main task file they belong to, while
+    // `Confirmation::RestoreDump` asks about them
+    pub emergency_dump: Option<(PathBuf, Dump)>,
+}
+
+/// How long the tasks have to stay unchanged before they are written, so a burst of edits is
+/// stored in one go.
+pub const AUTOSAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest wait before a failed write is tried again.
+pub const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// A question the user has to answer before an action goes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confirmation {
+    /// The task is about to be completed while some of its subtasks are still open.
+    CompleteSubtasks(Uuid),
+    /// The tasks are about to be replaced by the backup at the given position in `App::backups`.
+    RestoreBackup(usize),
+    /// The task file was damaged; the items salvaged from it can replace it or be browsed read-only.
+    UseSalvagedTasks,
+    /// The task file could not be read and is left alone, so the app runs read-only.
+    ReadOnly,
+    /// The task file was changed by another process; it can be reloaded, merged or overwritten.
+    ExternalChange,
+    /// The app crashed last time with unsaved tasks, which can be restored or thrown away.
+    RestoreDump,
+    /// The tasks are about to be replaced by those in the commit at the given position in
+    /// `App::history`.
+    RestoreRevision(usize),
 }
 
 // TODO: Refactor this into the models module - Could also be replaced by the TodoItem struct
@@ -40,12 +126,25 @@ pub struct NewTask {
 pub struct TodoList {
     pub items: Vec<TodoItem>,
     pub state: ListState,
+    // Whether tasks scheduled for a later day are listed
+    pub show_deferred: bool,
 }
 #[derive(PartialEq, Debug, Clone, Copy, Eq)]
 pub enum Mode {
     TaskList,
     Editing,
     Creating,
+    Confirming,
+    Linking,
+    Deferring,
+    TimeLog,
+    EditingTimeEntry,
+    Backups,
+    Lists,
+    NamingList,
+    AllLists,
+    History,
+    HistoryTasks,
 }
 
 impl Default for App {
@@ -74,7 +173,161 @@ impl Default for App {
                 due_date_temp: None,
                 tags: Vec::new(),
             }), // Initialize with an empty new task
+            pending_confirmation: None,
+            linking_task: None,
+            status_message: None,
+            prompt_input: String::new(),
+            time_log_state: ListState::default(),
+            editing_time_entry: None,
+            backups: Vec::new(),
+            backup_state: ListState::default(),
+            history: Vec::new(),
+            history_state: ListState::default(),
+            history_tasks: Vec::new(),
+            history_task_state: ListState::default(),
+            read_only: false,
+            load_problem: None,
+            saved_items: Vec::new(),
+            storage: Box::new(MemoryStorage::default()),
+            active_list: None,
+            list_choices: Vec::new(),
+            list_state: ListState::default(),
+            moving_task: None,
+            all_tasks: Vec::new(),
+            all_tasks_state: ListState::default(),
+            last_change: None,
+            save_error: None,
+            failed_writes: 0,
+            emergency_dump: None,
+        }
+    }
+}
+
+impl TodoList {
+    /// The rows of the tree view. The list selection is an index into these rows, not `items`.
+    pub fn rows(&self) -> Vec<TreeRow> {
+        let today = chrono::Local::now().date_naive();
+        tree::visible_rows(&self.items, |item| {
+            self.show_deferred || !item.is_deferred(today)
+        })
+    }
+
+    /// Sums the estimates of the open tasks in the current view, returning the total in minutes
+    /// and how many tasks have an estimate.
+    pub fn estimate_summary(&self) -> (u32, usize) {
+        self.rows()
+            .iter()
+            .map(|row| &self.items[row.index])
+            .filter(|item| !item.status.is_closed())
+            .filter_map(|item| item.estimate_minutes)
+            .fold((0, 0), |(total, count), minutes: u32| {
+                // Estimates read from a file may be as large as they like
+                (total.saturating_add(minutes), count + 1)
+            })
+    }
+
+    /// Returns the currently selected task, if the selection points at one.
+    pub fn selected(&self) -> Option<&TodoItem> {
+        let row = *self.rows().get(self.state.selected()?)?;
+        self.items.get(row.index)
+    }
+
+    pub fn selected_id(&self) -> Option<Uuid> {
+        self.selected().map(|item| item.id)
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&TodoItem> {
+        self.items.iter().find(|item| item.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: Uuid) -> Option<&mut TodoItem> {
+        self.items.iter_mut().find(|item| item.id == id)
+    }
+
+    /// Selects the task with the given ID, expanding its ancestors if they hide it.
+    pub fn select_id(&mut self, id: Uuid) {
+        let mut parent = self.get(id).and_then(|item| item.parent_id);
+        while let Some(parent_id) = parent {
+            parent = self.get_mut(parent_id).and_then(|item| {
+                item.collapsed = false;
+                item.parent_id
+            });
+        }
+        let rows = self.rows();
+        if let Some(row) = rows.iter().position(|row| self.items[row.index].id == id) {
+            self.state.select(Some(row));
+        }
+    }
+
+    /// Changes the status of a task. Completing a recurring task adds its next occurrence right
+    /// after it.
+    pub fn set_status(&mut self, id: Uuid, status: Status) {
+        let Some(i) = self.items.iter().position(|item| item.id == id) else {
+            return;
+        };
+        if !self.items[i].set_status(status) || !status.is_closed() {
+            return;
+        }
+        // Closing a task stops the clock on it
+        self.items[i].stop_timer(chrono::Utc::now());
+        if status != Status::Completed {
+            return;
         }
+        let today = chrono::Local::now().date_naive();
+        if let Some(next) = self.items[i].next_occurrence(today) {
+            self.items.insert(i + 1, next);
+        }
+    }
+
+    /// Whether any subtask of the task, at any depth, is still open.
+    pub fn has_open_descendants(&self, id: Uuid) -> bool {
+        tree::descendants(&self.items, id)
+            .into_iter()
+            .filter_map(|child| self.get(child))
+            .any(|child| !child.status.is_closed())
+    }
+
+    /// Makes the task a subtask of the sibling directly above it.
+    pub fn indent(&mut self, id: Uuid) {
+        let Some(parent_id) = self.get(id).map(|item| item.parent_id) else {
+            return;
+        };
+        let previous_sibling = self
+            .items
+            .iter()
+            .take_while(|item| item.id != id)
+            .filter(|item| item.parent_id == parent_id)
+            .last()
+            .map(|item| item.id);
+        if let Some(new_parent) = previous_sibling {
+            if let Some(parent) = self.get_mut(new_parent) {
+                parent.collapsed = false;
+            }
+            if let Some(item) = self.get_mut(id) {
+                item.parent_id = Some(new_parent);
+                item.touch();
+            }
+        }
+    }
+
+    /// Moves the task up one level, placing it right after its former parent.
+    pub fn outdent(&mut self, id: Uuid) {
+        let Some(parent_id) = self.get(id).and_then(|item| item.parent_id) else {
+            return;
+        };
+        let grandparent_id = self.get(parent_id).and_then(|parent| parent.parent_id);
+        let Some(from) = self.items.iter().position(|item| item.id == id) else {
+            return;
+        };
+        let mut item = self.items.remove(from);
+        item.parent_id = grandparent_id;
+        item.touch();
+        let to = self
+            .items
+            .iter()
+            .position(|item| item.id == parent_id)
+            .map_or(self.items.len(), |i| i + 1);
+        self.items.insert(to, item);
     }
 }
 
@@ -85,7 +338,11 @@ impl FromIterator<(Status, &'static str, &'static str)> for TodoList {
             .map(|(status, todo, info)| TodoItem::new(status, todo, info))
             .collect();
         let state = ListState::default();
-        Self { items, state }
+        Self {
+            items,
+            state,
+            show_deferred: false,
+        }
     }
 }
 
@@ -100,15 +357,33 @@ impl App {
         self.cursor_visible = !self.cursor_visible;
     }
 
-    pub fn load_or_default() -> Self {
-        let storage_path = get_default_storage_path();
-        match load_from_file(&storage_path) {
-            Ok(todo_data) => Self {
+    /// Loads the stored tasks, or the demo tasks when nothing has been stored yet. Data from a
+    /// newer version is refused, and any other failure starts the recovery in `App::recover`, so
+    /// the stored tasks are never replaced with the demo tasks on exit. If another TaskMaster is
+    /// already using the storage, this one only shows it read-only.
+    pub fn load_or_default(mut storage: Box<dyn Storage>) -> io::Result<Self> {
+        // Without a lock, e.g. in a read-only directory, there is nothing to coordinate with
+        let in_use = !storage.lock().unwrap_or(true);
+        let in_use_problem = format!(
+            "Another TaskMaster is already using {}, so this one is read-only to keep either from \
+             overwriting the other.",
+            storage.describe()
+        );
+
+        let mut app = match storage.load() {
+            Ok(items) => Self {
+                // Keep a copy of the tasks as they were when the session started
+                status_message: storage
+                    .backup()
+                    .err()
+                    .map(|error| format!("Could not back up the tasks: {}", error)),
                 cursor_visible: true,
                 should_exit: false,
+                saved_items: items.clone(),
                 todo_list: TodoList {
-                    items: todo_data.items,
+                    items,
                     state: ListState::default(),
+                    show_deferred: false,
                 },
                 current_mode: Mode::TaskList,
                 editing_task: None,
@@ -120,17 +395,303 @@ impl App {
                     due_date_temp: None,
                     tags: Vec::new(),
                 }),
+                pending_confirmation: None,
+                linking_task: None,
+                prompt_input: String::new(),
+                time_log_state: ListState::default(),
+                editing_time_entry: None,
+                backups: Vec::new(),
+                backup_state: ListState::default(),
+                history: Vec::new(),
+                history_state: ListState::default(),
+                history_tasks: Vec::new(),
+                history_task_state: ListState::default(),
+                read_only: false,
+                load_problem: None,
+                storage,
+                active_list: None,
+                list_choices: Vec::new(),
+                list_state: ListState::default(),
+                moving_task: None,
+                all_tasks: Vec::new(),
+                all_tasks_state: ListState::default(),
+                last_change: None,
+                save_error: None,
+                failed_writes: 0,
+                emergency_dump: None,
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Self {
+                storage,
+                ..Self::default()
             },
-            Err(_) => Self::default(),
+            Err(error) if error.kind() == io::ErrorKind::Unsupported => {
+                return Err(io::Error::new(
+                    error.kind(),
+                    format!("Could not load {}: {}", storage.describe(), error),
+                ))
+            }
+            // The other TaskMaster may be in the middle of dealing with the data, so leave it be
+            Err(error) if in_use => {
+                let problem = format!(
+                    "Could not load {}: {}\n\n{}",
+                    storage.describe(),
+                    error,
+                    in_use_problem
+                );
+                Self::read_only_view(storage, problem)
+            }
+            Err(error) => Self::recover(storage, error),
+        };
+        if in_use && !app.read_only {
+            app.read_only = true;
+            app.load_problem = Some(in_use_problem);
+            app.pending_confirmation = Some(Confirmation::ReadOnly);
+            app.current_mode = Mode::Confirming;
         }
+        Ok(app)
     }
 
-    pub fn save(&self) -> io::Result<()> {
-        let storage_path = get_default_storage_path();
-        let todo_data = TodoData {
-            items: self.todo_list.items.clone(),
+    /// Starts from stored tasks that could not be loaded. The tasks that can still be read are
+    /// salvaged from damaged data, which stays where it is until the user accepts them. Either way
+    /// the app stays read-only until the user accepts the salvaged tasks or restores a backup.
+    pub fn recover(storage: Box<dyn Storage>, error: io::Error) -> Self {
+        let problem = format!("Could not load {}: {}", storage.describe(), error);
+
+        // Other errors, e.g. missing permissions, say nothing about the data itself. A file cut
+        // off mid-write shows up as an unexpected end of file.
+        if !matches!(
+            error.kind(),
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof
+        ) {
+            return Self::read_only_view(storage, problem);
+        }
+        match storage.salvage() {
+            Ok(salvage) => {
+                let mut todo_data = TodoData::new(salvage.items);
+                todo_data.ensure_unique_ids();
+                let problem = format!(
+                    "{}\n\n{} tasks could be salvaged, {} could not. Continuing with them moves the damaged data aside.",
+                    problem,
+                    todo_data.items.len(),
+                    salvage.skipped
+                );
+                let mut app = Self::read_only_view(storage, problem);
+                app.pending_confirmation = Some(Confirmation::UseSalvagedTasks);
+                app.todo_list.items = todo_data.items;
+                app.todo_list.state.select_first();
+                app
+            }
+            Err(error) => {
+                let problem = format!("{}\n\nNothing could be salvaged: {}", problem, error);
+                Self::read_only_view(storage, problem)
+            }
+        }
+    }
+
+    // Starts read-only without any tasks, explaining why in a popup
+    fn read_only_view(storage: Box<dyn Storage>, problem: String) -> Self {
+        let mut app = Self {
+            current_mode: Mode::Confirming,
+            pending_confirmation: Some(Confirmation::ReadOnly),
+            read_only: true,
+            load_problem: Some(problem),
+            storage,
+            ..Self::default()
         };
-        save_to_file(&storage_path, &todo_data)
+        app.todo_list.items.clear();
+        app
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        // Never replace data that could not be loaded with what the app happens to show
+        if self.read_only {
+            return Ok(());
+        }
+        // Fold in what another process saved since the tasks were last read instead of dropping it
+        if self.storage.changed_externally() {
+            let theirs = self.storage.load()?;
+            self.todo_list.items = merge::merge(&self.saved_items, &self.todo_list.items, &theirs);
+        }
+        self.storage.save(&self.todo_list.items)?;
+        self.saved_items = self.todo_list.items.clone();
+        self.save_error = None;
+        Ok(())
+    }
+
+    /// Whether the tasks shown differ from the stored ones.
+    pub fn has_unsaved_changes(&self) -> bool {
+        !self.read_only && self.todo_list.items != self.saved_items
+    }
+
+    /// Notes the time of a change, restarting the wait before it is written. Called after every
+    /// key press.
+    pub fn note_changes(&mut self, now: Instant) {
+        if self.has_unsaved_changes() {
+            self.last_change = Some(now);
+        }
+    }
+
+    /// Writes the changes once none were made for `AUTOSAVE_DELAY`. Called from the main loop.
+    /// While the user decides what to do about an outside change, nothing is written. A failed
+    /// write is tried again, waiting twice as long after each failure up to `MAX_RETRY_DELAY`.
+    pub fn autosave(&mut self, now: Instant) {
+        let delay = AUTOSAVE_DELAY
+            .saturating_mul(1 << self.failed_writes.min(8))
+            .min(MAX_RETRY_DELAY);
+        let due = self
+            .last_change
+            .is_some_and(|changed| now.duration_since(changed) >= delay);
+        if due && self.pending_confirmation != Some(Confirmation::ExternalChange) {
+            self.last_change = None;
+            self.write_changes();
+            if self.save_error.is_some() && self.has_unsaved_changes() {
+                self.failed_writes += 1;
+                self.last_change = Some(now);
+            } else {
+                self.failed_writes = 0;
+            }
+        }
+    }
+
+    /// Writes the tasks that changed since the last write through to storage, one by one.
+    /// Everything is saved instead when tasks were reordered, there is no stored list to update
+    /// yet or it was changed outside the app.
+    pub fn write_changes(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let result = match self.changes_since_saved() {
+            Some((changed, deleted)) if changed.is_empty() && deleted.is_empty() => Ok(()),
+            Some((changed, deleted))
+                if !self.storage.changed_externally()
+                    && self.write_items(&changed, &deleted).is_ok() =>
+            {
+                Ok(())
+            }
+            _ => self.save(),
+        };
+        self.save_error = result.err().map(|error| error.to_string());
+    }
+
+    // The IDs of the tasks added or changed since the last write and of those removed, or `None`
+    // if tasks were moved around, as single updates only append new tasks at the end
+    fn changes_since_saved(&self) -> Option<(Vec<Uuid>, Vec<Uuid>)> {
+        let saved: HashMap<Uuid, &TodoItem> = self
+            .saved_items
+            .iter()
+            .map(|item| (item.id, item))
+            .collect();
+        let current: HashSet<Uuid> = self.todo_list.items.iter().map(|item| item.id).collect();
+
+        let kept = self
+            .todo_list
+            .items
+            .iter()
+            .map(|item| item.id)
+            .filter(|id| saved.contains_key(id));
+        let still_there = self
+            .saved_items
+            .iter()
+            .map(|item| item.id)
+            .filter(|id| current.contains(id));
+        let new_at_end = self
+            .todo_list
+            .items
+            .iter()
+            .skip_while(|item| saved.contains_key(&item.id))
+            .all(|item| !saved.contains_key(&item.id));
+        if !kept.eq(still_there) || !new_at_end {
+            return None;
+        }
+
+        let changed = self
+            .todo_list
+            .items
+            .iter()
+            .filter(|item| saved.get(&item.id).is_none_or(|saved| *saved != *item))
+            .map(|item| item.id)
+            .collect();
+        let deleted = self
+            .saved_items
+            .iter()
+            .map(|item| item.id)
+            .filter(|id| !current.contains(id))
+            .collect();
+        Some((changed, deleted))
+    }
+
+    // Upserts and deletes single tasks, keeping `saved_items` in step with storage
+    fn write_items(&mut self, changed: &[Uuid], deleted: &[Uuid]) -> io::Result<()> {
+        for &id in deleted {
+            self.storage.delete(id)?;
+            self.saved_items.retain(|saved| saved.id != id);
+        }
+        for &id in changed {
+            let Some(item) = self.todo_list.get(id) else {
+                continue;
+            };
+            self.storage.upsert(item)?;
+            match self.saved_items.iter_mut().find(|saved| saved.id == id) {
+                Some(saved) => *saved = item.clone(),
+                None => self.saved_items.push(item.clone()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks what to do when the stored tasks were changed outside the app. Called from the main
+    /// loop; the question waits while a form or popup is open.
+    pub fn check_for_external_changes(&mut self) {
+        if self.current_mode == Mode::TaskList && self.storage.changed_externally() {
+            self.pending_confirmation = Some(Confirmation::ExternalChange);
+            self.current_mode = Mode::Confirming;
+        }
+    }
+
+    /// Replaces the tasks with the stored ones, dropping unsaved changes made here.
+    pub fn reload_from_disk(&mut self) {
+        self.resolve_external_change(|_, _, theirs| theirs.to_vec());
+    }
+
+    /// Combines the stored tasks with the ones here and saves the result.
+    pub fn merge_from_disk(&mut self) {
+        self.resolve_external_change(merge::merge);
+        if let Err(error) = self.save() {
+            self.status_message = Some(format!("Could not save: {}", error));
+        }
+    }
+
+    /// Keeps the tasks here and saves them over the changed ones.
+    pub fn keep_own_changes(&mut self) {
+        self.pending_confirmation = None;
+        self.current_mode = Mode::TaskList;
+        self.storage.mark_seen();
+        if let Err(error) = self.save() {
+            self.status_message = Some(format!("Could not save: {}", error));
+        }
+    }
+
+    // Reads the stored tasks again and combines them with the ones here as
+    // `combine(base, mine, theirs)`
+    fn resolve_external_change(
+        &mut self,
+        combine: impl Fn(&[TodoItem], &[TodoItem], &[TodoItem]) -> Vec<TodoItem>,
+    ) {
+        self.pending_confirmation = None;
+        self.current_mode = Mode::TaskList;
+        match self.storage.load() {
+            Ok(theirs) => {
+                let selected = self.todo_list.selected_id();
+                self.todo_list.items = combine(&self.saved_items, &self.todo_list.items, &theirs);
+                self.saved_items = theirs;
+                if let Some(id) = selected {
+                    self.todo_list.select_id(id);
+                }
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not read the changed tasks: {}", error));
+            }
+        }
     }
 
     pub fn create_new_task(&mut self) {
@@ -140,49 +701,71 @@ impl App {
 
         // Provide a blank template for the new task
         self.editing_task = Some(TodoItem {
-            todo: String::new(),
-            info: String::new(),
-            status: Status::Todo,
-            due_date: None,
-            tags: vec![],
             due_date_temp: Some(String::new()),
+            recurrence_temp: Some(String::new()),
+            estimate_temp: Some(String::new()),
+            ..TodoItem::new(Status::Todo, "", "")
         });
     }
 
+    /// Opens the form for a new task that is created as a subtask of the selected one.
+    pub fn create_new_subtask(&mut self) {
+        if let Some(parent_id) = self.todo_list.selected_id() {
+            self.create_new_task();
+            if let Some(task) = &mut self.editing_task {
+                task.parent_id = Some(parent_id);
+            }
+        }
+    }
+
     pub fn save_new_task(&mut self) {
-        if let Some(new_task) = self.editing_task.take() {
+        if let Some(mut new_task) = self.editing_task.take() {
+            // The task counts as created when it is saved, not when the form was opened
+            new_task.created_at = chrono::Utc::now();
+            new_task.updated_at = new_task.created_at;
+            self.apply_form_fields(&mut new_task);
+
             // Add the new task to the list
+            let id = new_task.id;
             self.todo_list.items.push(new_task);
             self.current_mode = Mode::TaskList;
 
             // Select the newly added task
-            self.todo_list.state.select_last();
+            self.todo_list.select_id(id);
         }
     }
 
     pub fn delete_selected_task(&mut self) {
-        if let Some(selected) = self.todo_list.state.selected() {
+        if let Some(id) = self.todo_list.selected_id() {
+            // Subtasks of the deleted task move up to its parent instead of being lost
+            let parent_id = self.todo_list.get(id).and_then(|item| item.parent_id);
+            for item in &mut self.todo_list.items {
+                if item.parent_id == Some(id) {
+                    item.parent_id = parent_id;
+                }
+            }
+
+            // Drop dependency links pointing at the deleted task
+            for item in &mut self.todo_list.items {
+                item.depends_on.retain(|prerequisite| *prerequisite != id);
+            }
+
             // Remove the task from current state
-            self.todo_list.items.remove(selected);
+            self.todo_list.items.retain(|item| item.id != id);
 
             // Reset the selected state to avaoid out-of-bounds selections
             self.todo_list.state.select_first();
-
-            // Persist the updated state to the localfile
-            if let Err(e) = self.save() {
-                eprintln!("Failed to save the updated state: {}", e);
-            }
         }
     }
 
     pub fn enter_editing_mode(&mut self) {
-        if let Some(selected) = self.todo_list.state.selected() {
+        if let Some(task) = self.todo_list.selected() {
+            let mut task = task.clone();
             self.current_mode = Mode::Editing;
-            let mut task = self.todo_list.items[selected].clone();
-            // Initialize due_date_temp with the existing due_date if present
-            task.due_date_temp = task
-                .due_date
-                .map(|date| date.format("%Y-%m-%d").to_string());
+            // Initialize due_date_temp with the existing due date and time if present
+            task.due_date_temp = task.due().map(|due| due.to_string());
+            task.recurrence_temp = task.recurrence.as_ref().map(Recurrence::describe);
+            task.estimate_temp = task.estimate_minutes.map(time_tracking::format_minutes);
             self.editing_task = Some(task);
             self.current_editing_field = EditingField::TaskName;
         }
@@ -192,7 +775,10 @@ impl App {
         self.current_editing_field = match self.current_editing_field {
             EditingField::TaskName => EditingField::Description,
             EditingField::Description => EditingField::DueDate,
-            EditingField::DueDate => EditingField::Tags,
+            EditingField::DueDate => EditingField::Estimate,
+            EditingField::Estimate => EditingField::Recurrence,
+            EditingField::Recurrence => EditingField::Priority,
+            EditingField::Priority => EditingField::Tags,
             EditingField::Tags => EditingField::TaskName,
         };
     }
@@ -220,6 +806,26 @@ impl App {
                     new_task.due_date_temp.get_or_insert(String::new()).push(c);
                 }
             }
+            EditingField::Estimate => {
+                if let Some(task) = &mut self.editing_task {
+                    task.estimate_temp.get_or_insert(String::new()).push(c);
+                }
+            }
+            EditingField::Recurrence => {
+                if let Some(task) = &mut self.editing_task {
+                    task.recurrence_temp.get_or_insert(String::new()).push(c);
+                }
+            }
+            EditingField::Priority => {
+                // Digits pick a level directly, +/- or space step through them
+                if let Some(task) = &mut self.editing_task {
+                    task.priority = match c {
+                        '+' | ' ' => task.priority.next(),
+                        '-' => task.priority.previous(),
+                        _ => Priority::from_digit(c).unwrap_or(task.priority),
+                    };
+                }
+            }
             EditingField::Tags => {
                 if let Some(task) = &mut self.editing_task {
                     task.tags.push(c.to_string());
@@ -257,6 +863,25 @@ impl App {
                     }
                 }
             }
+            EditingField::Estimate => {
+                if let Some(task) = &mut self.editing_task {
+                    if let Some(ref mut estimate_temp) = task.estimate_temp {
+                        estimate_temp.pop();
+                    }
+                }
+            }
+            EditingField::Recurrence => {
+                if let Some(task) = &mut self.editing_task {
+                    if let Some(ref mut recurrence_temp) = task.recurrence_temp {
+                        recurrence_temp.pop();
+                    }
+                }
+            }
+            EditingField::Priority => {
+                if let Some(task) = &mut self.editing_task {
+                    task.priority = Priority::None;
+                }
+            }
             EditingField::Tags => {
                 if let Some(task) = &mut self.editing_task {
                     task.tags.pop();
@@ -269,13 +894,13 @@ impl App {
 
     pub fn save_task(&mut self) {
         if self.current_mode == Mode::Editing {
-            if let Some(selected) = self.todo_list.state.selected() {
-                if let Some(mut editing_task) = self.editing_task.take() {
-                    // Parse the due date string into NaiveDate
-                    if let Some(due_date_str) = &editing_task.due_date_temp {
-                        editing_task.due_date = Self::parse_due_date(due_date_str);
-                    }
-                    self.todo_list.items[selected] = editing_task.clone();
+            if let Some(mut editing_task) = self.editing_task.take() {
+                self.apply_form_fields(&mut editing_task);
+                editing_task.touch();
+                // Look the task up by ID rather than by the current selection
+                let id = editing_task.id;
+                if let Some(task) = self.todo_list.get_mut(id) {
+                    *task = editing_task;
                 }
             }
             self.current_mode = Mode::TaskList;
@@ -283,15 +908,42 @@ impl App {
         }
     }
 
-    // Utility function to parse different date formats and keywords
-    fn parse_due_date(input: &str) -> Option<NaiveDate> {
-        match input.trim().to_lowercase().as_str() {
-            "today" => Some(chrono::Local::now().naive_local().date()), // Parse "today"
-            "tomorrow" => {
-                Some(chrono::Local::now().naive_local().date() + chrono::Duration::days(1))
-            } // Parse "tomorrow"
-            _ => NaiveDate::parse_from_str(input, "%Y-%m-%d").ok(), // Try to parse as "YYYY-MM-DD"
+    // Turns the free-text form fields into their typed counterparts
+    fn apply_form_fields(&mut self, task: &mut TodoItem) {
+        // Parse the due date string into a date or date and time
+        if let Some(due_date_str) = &task.due_date_temp {
+            task.set_due(Self::parse_due_date(due_date_str));
+        }
+        if let Some(estimate) = task.estimate_temp.take() {
+            if estimate.trim().is_empty() {
+                task.estimate_minutes = None;
+            } else if let Some(minutes) = time_tracking::parse_estimate(&estimate) {
+                task.estimate_minutes = Some(minutes);
+            } else {
+                self.status_message = Some(format!(
+                    "Could not understand estimate \"{}\" (e.g. 30m, 1h30m or 2d, up to {}d)",
+                    estimate,
+                    time_tracking::MAX_ESTIMATE_DAYS
+                ));
+            }
         }
+        if let Some(rule) = task.recurrence_temp.take() {
+            if rule.trim().is_empty() {
+                task.recurrence = None;
+            } else if let Some(recurrence) = Recurrence::parse(&rule) {
+                task.recurrence = Some(recurrence);
+            } else {
+                // Keep the previous rule rather than silently dropping it
+                self.status_message =
+                    Some(format!("Could not understand repeat rule \"{}\"", rule));
+            }
+        }
+    }
+
+    // Utility function to parse different date formats and keywords, e.g. "today",
+    // "tomorrow 14:00", "2026-11-02" or "2026-11-02T09:30+01:00"
+    fn parse_due_date(input: &str) -> Option<Due> {
+        Due::parse(input, chrono::Local::now())
     }
 
     pub fn cancel_editing(&mut self) {
@@ -335,13 +987,954 @@ impl App {
         self.todo_list.state.select_last();
     }
 
-    /// Changes the status of the selected list item
+    /// Orders the list by priority, most important first, keeping the current selection.
+    /// The sort is stable, so tasks with equal priority keep their relative order.
+    pub fn sort_by_priority(&mut self) {
+        let selected = self.todo_list.selected_id();
+        self.todo_list
+            .items
+            .sort_by_key(|item| std::cmp::Reverse(item.priority));
+        if let Some(id) = selected {
+            self.todo_list.select_id(id);
+        }
+    }
+
+    /// Marks the selected task as completed, or reopens it if it is already closed
     pub fn toggle_status(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            self.todo_list.items[i].status = match self.todo_list.items[i].status {
-                Status::Completed => Status::Todo,
-                Status::Todo => Status::Completed,
+        if let Some((id, status)) = self.todo_list.selected().map(|t| (t.id, t.status)) {
+            let status = if status.is_closed() {
+                Status::Todo
+            } else {
+                Status::Completed
+            };
+            self.change_status(id, status);
+        }
+    }
+
+    /// Steps the selected task to the next reachable status.
+    pub fn cycle_status(&mut self) {
+        if let Some((id, status)) = self.todo_list.selected().map(|t| (t.id, t.status)) {
+            self.change_status(id, status.next());
+        }
+    }
+
+    /// Applies a status change, asking first when completing a task with open subtasks.
+    fn change_status(&mut self, id: Uuid, status: Status) {
+        if status == Status::Completed && self.todo_list.has_open_descendants(id) {
+            self.pending_confirmation = Some(Confirmation::CompleteSubtasks(id));
+            self.current_mode = Mode::Confirming;
+        } else {
+            self.todo_list.set_status(id, status);
+        }
+    }
+
+    /// Answers the pending confirmation. Declining still performs the action in its narrower
+    /// form where there is one, e.g. completing only the parent task and leaving its subtasks open.
+    pub fn answer_confirmation(&mut self, accepted: bool) {
+        match self.pending_confirmation.take() {
+            Some(Confirmation::CompleteSubtasks(id)) => {
+                let mut ids = vec![id];
+                if accepted {
+                    ids.extend(tree::descendants(&self.todo_list.items, id));
+                }
+                for id in ids {
+                    if self
+                        .todo_list
+                        .get(id)
+                        .is_some_and(|task| !task.status.is_closed())
+                    {
+                        self.todo_list.set_status(id, Status::Completed);
+                    }
+                }
+            }
+            Some(Confirmation::RestoreBackup(i)) if accepted => self.restore_backup(i),
+            Some(Confirmation::RestoreDump) => self.restore_dump(accepted),
+            Some(Confirmation::RestoreRevision(i)) if accepted => self.restore_revision(i),
+            Some(Confirmation::UseSalvagedTasks) if accepted => match self.storage.quarantine() {
+                Ok(moved_to) => {
+                    self.read_only = false;
+                    self.load_problem = None;
+                    self.status_message = Some(match self.save() {
+                        Ok(()) => format!("The damaged data was moved to {}", moved_to),
+                        Err(error) => format!("Could not save: {}", error),
+                    });
+                }
+                Err(error) => {
+                    self.status_message =
+                        Some(format!("Could not move the damaged data aside: {}", error));
+                }
+            },
+            Some(
+                Confirmation::RestoreBackup(_)
+                | Confirmation::RestoreRevision(_)
+                | Confirmation::UseSalvagedTasks
+                | Confirmation::ReadOnly
+                | Confirmation::ExternalChange,
+            ) => {}
+            None => {}
+        }
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn cancel_confirmation(&mut self) {
+        self.pending_confirmation = None;
+        // A dump that wasn't decided on stays on disk for the next start
+        self.emergency_dump = None;
+        self.current_mode = Mode::TaskList;
+    }
+
+    /// Starts picking a prerequisite for the selected task.
+    pub fn start_linking(&mut self) {
+        if let Some(id) = self.todo_list.selected_id() {
+            self.linking_task = Some(id);
+            self.current_mode = Mode::Linking;
+        }
+    }
+
+    /// Makes the task being linked depend on the selected task, or removes the link if it
+    /// already exists. Links that would make tasks wait on each other are refused.
+    pub fn toggle_dependency(&mut self) {
+        let (Some(task_id), Some(prerequisite)) = (self.linking_task, self.todo_list.selected())
+        else {
+            return self.cancel_linking();
+        };
+        let (prerequisite_id, prerequisite_name) = (prerequisite.id, prerequisite.todo.clone());
+
+        if prerequisite_id == task_id {
+            self.status_message = Some("A task cannot depend on itself".to_string());
+        } else if self
+            .todo_list
+            .get(task_id)
+            .is_some_and(|task| task.depends_on.contains(&prerequisite_id))
+        {
+            if let Some(task) = self.todo_list.get_mut(task_id) {
+                task.depends_on.retain(|id| *id != prerequisite_id);
+                task.touch();
+            }
+            self.status_message = Some(format!("No longer waiting for \"{}\"", prerequisite_name));
+        } else if dependencies::would_create_cycle(&self.todo_list.items, task_id, prerequisite_id)
+        {
+            self.status_message = Some(format!(
+                "Cannot link: \"{}\" already waits for this task",
+                prerequisite_name
+            ));
+        } else if let Some(task) = self.todo_list.get_mut(task_id) {
+            task.depends_on.push(prerequisite_id);
+            task.touch();
+            self.status_message = Some(format!("Now waiting for \"{}\"", prerequisite_name));
+        }
+
+        self.cancel_linking();
+    }
+
+    /// Leaves linking mode and returns the selection to the task being linked.
+    pub fn cancel_linking(&mut self) {
+        if let Some(id) = self.linking_task.take() {
+            self.todo_list.select_id(id);
+        }
+        self.current_mode = Mode::TaskList;
+    }
+
+    /// Shows or hides tasks that are scheduled for a later day.
+    pub fn toggle_show_deferred(&mut self) {
+        let selected = self.todo_list.selected_id();
+        self.todo_list.show_deferred = !self.todo_list.show_deferred;
+        match selected {
+            Some(id) => self.todo_list.select_id(id),
+            None => self.todo_list.state.select_first(),
+        }
+    }
+
+    /// Opens the prompt asking how long to defer the selected task for.
+    pub fn start_deferring(&mut self) {
+        if self.todo_list.selected().is_some() {
+            self.prompt_input.clear();
+            self.current_mode = Mode::Deferring;
+        }
+    }
+
+    pub fn prompt_input(&mut self, c: char) {
+        self.prompt_input.push(c);
+    }
+
+    pub fn backspace_prompt_input(&mut self) {
+        self.prompt_input.pop();
+    }
+
+    /// Pushes the scheduled date of the selected task forward by the amount typed into the
+    /// prompt (`3d`, `2w`, `1m`), counting from its current start date if that is still ahead, or
+    /// sets it to a date such as `2026-11-02` or `tomorrow`. `now` makes the task current again.
+    pub fn apply_defer(&mut self) {
+        self.current_mode = Mode::TaskList;
+        let input = std::mem::take(&mut self.prompt_input);
+        let Some(id) = self.todo_list.selected_id() else {
+            return;
+        };
+        let now = chrono::Local::now();
+        let today = now.date_naive();
+
+        let scheduled = if input.trim().eq_ignore_ascii_case("now") {
+            None
+        } else {
+            let from = self
+                .todo_list
+                .get(id)
+                .and_then(|task| task.scheduled)
+                .filter(|scheduled| *scheduled > today)
+                .unwrap_or(today);
+            match due::add_amount(from, &input).or_else(|| Due::parse(&input, now).map(Due::date)) {
+                Some(date) => Some(date),
+                None => {
+                    self.status_message = Some(format!("Could not understand \"{}\"", input));
+                    return;
+                }
+            }
+        };
+
+        if let Some(task) = self.todo_list.get_mut(id) {
+            task.scheduled = scheduled;
+            task.touch();
+        }
+        self.status_message = Some(match scheduled {
+            Some(date) if !self.todo_list.show_deferred && date > today => {
+                format!("Deferred until {}, press 'v' to show deferred tasks", date)
+            }
+            Some(date) => format!("Deferred until {}", date),
+            None => "No longer deferred".to_string(),
+        });
+        self.todo_list.select_id(id);
+    }
+
+    /// Applies whatever the open prompt was asking for.
+    pub fn submit_prompt(&mut self) {
+        match self.current_mode {
+            Mode::Deferring => self.apply_defer(),
+            Mode::EditingTimeEntry => self.apply_time_entry(),
+            Mode::NamingList => self.apply_new_list(),
+            _ => {}
+        }
+    }
+
+    pub fn cancel_prompt(&mut self) {
+        self.prompt_input.clear();
+        self.current_mode = match self.current_mode {
+            Mode::EditingTimeEntry => Mode::TimeLog,
+            Mode::NamingList => Mode::Lists,
+            _ => Mode::TaskList,
+        };
+    }
+
+    /// Starts the timer on the selected task, stopping any other running timer, or stops it if
+    /// it is already running on this task.
+    pub fn toggle_timer(&mut self) {
+        let Some(id) = self.todo_list.selected_id() else {
+            return;
+        };
+        let now = chrono::Utc::now();
+        let was_running = self
+            .todo_list
+            .get(id)
+            .is_some_and(TodoItem::is_timer_running);
+        for task in &mut self.todo_list.items {
+            task.stop_timer(now);
+        }
+        if !was_running {
+            if let Some(task) = self.todo_list.get_mut(id) {
+                task.start_timer(now);
+            }
+        }
+    }
+
+    /// Opens the list of time intervals logged on the selected task.
+    pub fn open_time_log(&mut self) {
+        if let Some(task) = self.todo_list.selected() {
+            let last = task.time_log.len().checked_sub(1);
+            self.time_log_state.select(last);
+            self.current_mode = Mode::TimeLog;
+        }
+    }
+
+    pub fn close_time_log(&mut self) {
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn select_next_time_entry(&mut self) {
+        self.time_log_state.select_next();
+    }
+
+    pub fn select_previous_time_entry(&mut self) {
+        self.time_log_state.select_previous();
+    }
+
+    // Index of the selected interval, clamped to the entries of the selected task
+    fn selected_time_entry(&self) -> Option<usize> {
+        let len = self.todo_list.selected()?.time_log.len();
+        self.time_log_state
+            .selected()
+            .filter(|_| len > 0)
+            .map(|i| i.min(len - 1))
+    }
+
+    pub fn delete_time_entry(&mut self) {
+        let (Some(id), Some(i)) = (self.todo_list.selected_id(), self.selected_time_entry()) else {
+            return;
+        };
+        if let Some(task) = self.todo_list.get_mut(id) {
+            task.time_log.remove(i);
+            task.touch();
+        }
+    }
+
+    /// Opens a prompt with the selected interval, or a new one ending now if `new` is set.
+    pub fn start_editing_time_entry(&mut self, new: bool) {
+        let Some(task) = self.todo_list.selected() else {
+            return;
+        };
+        self.editing_time_entry = if new {
+            None
+        } else {
+            match self.selected_time_entry() {
+                Some(i) => Some(i),
+                None => return,
             }
+        };
+        let entry = match self.editing_time_entry {
+            Some(i) => task.time_log[i],
+            None => {
+                let now = chrono::Utc::now();
+                TimeEntry {
+                    start: now - chrono::Duration::hours(1),
+                    end: Some(now),
+                }
+            }
+        };
+        self.prompt_input = entry.describe();
+        self.current_mode = Mode::EditingTimeEntry;
+    }
+
+    fn apply_time_entry(&mut self) {
+        self.current_mode = Mode::TimeLog;
+        let input = std::mem::take(&mut self.prompt_input);
+        let editing = self.editing_time_entry.take();
+        let Some(entry) = TimeEntry::parse(&input, chrono::Local::now()) else {
+            self.status_message = Some(format!("Could not understand \"{}\"", input));
+            return;
+        };
+        let Some(id) = self.todo_list.selected_id() else {
+            return;
+        };
+        let now = chrono::Utc::now();
+        // Only one timer may run at a time, so a running entry stops every other one
+        if entry.is_running() {
+            for task in &mut self.todo_list.items {
+                task.stop_timer(now);
+            }
+        }
+        if let Some(task) = self.todo_list.get_mut(id) {
+            match editing {
+                Some(i) if i < task.time_log.len() => task.time_log[i] = entry,
+                _ => task.time_log.push(entry),
+            }
+            task.time_log.sort_by_key(|entry| entry.start);
+            let selected = task.time_log.iter().position(|e| *e == entry);
+            task.touch();
+            self.time_log_state.select(selected);
+        }
+    }
+
+    /// Opens the list of backups of the task file.
+    pub fn open_backups(&mut self) {
+        match self.storage.list_backups() {
+            Ok(backups) if backups.is_empty() => {
+                self.status_message = Some("There are no backups yet".to_string());
+            }
+            Ok(backups) => {
+                self.backups = backups;
+                self.backup_state.select(Some(0));
+                self.current_mode = Mode::Backups;
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not list backups: {}", error));
+            }
+        }
+    }
+
+    pub fn close_backups(&mut self) {
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn select_next_backup(&mut self) {
+        self.backup_state.select_next();
+    }
+
+    pub fn select_previous_backup(&mut self) {
+        self.backup_state.select_previous();
+    }
+
+    /// Asks before replacing the tasks with the selected backup.
+    pub fn confirm_restore_backup(&mut self) {
+        if let Some(i) = self
+            .backup_state
+            .selected()
+            .filter(|_| !self.backups.is_empty())
+        {
+            let i = i.min(self.backups.len() - 1);
+            self.pending_confirmation = Some(Confirmation::RestoreBackup(i));
+            self.current_mode = Mode::Confirming;
+        }
+    }
+
+    // Replaces the tasks with those in a backup. The current tasks are backed up first, so a
+    // restore can itself be undone from the backup list.
+    fn restore_backup(&mut self, i: usize) {
+        let Some(chosen) = self.backups.get(i).cloned() else {
+            return;
+        };
+        // Restoring writes the tasks, so they must not be in use by another TaskMaster
+        if let Ok(false) = self.storage.lock() {
+            self.status_message = Some(
+                "Another TaskMaster is using the tasks; close it before restoring".to_string(),
+            );
+            return;
+        }
+        let result = self.storage.restore_backup(&chosen).and_then(|items| {
+            self.todo_list.items = items;
+            // Whatever is stored now is replaced on purpose
+            self.storage.mark_seen();
+            self.read_only = false;
+            self.load_problem = None;
+            self.todo_list.state.select_first();
+            self.save()
+        });
+        self.status_message = Some(match result {
+            Ok(()) => format!(
+                "Restored the backup from {}",
+                chosen.created.format("%Y-%m-%d %H:%M:%S")
+            ),
+            Err(error) => format!("Could not restore the backup: {}", error),
+        });
+    }
+
+    /// Opens the list of commits that changed the shown list, if the tasks are kept in git.
+    pub fn open_history(&mut self) {
+        match self.storage.history() {
+            Ok(history) if history.is_empty() => {
+                self.status_message = Some("There is no history of this list yet".to_string());
+            }
+            Ok(history) => {
+                self.history = history;
+                self.history_state.select(Some(0));
+                self.current_mode = Mode::History;
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not read the history: {}", error));
+            }
+        }
+    }
+
+    pub fn close_history(&mut self) {
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn select_next_revision(&mut self) {
+        self.history_state.select_next();
+    }
+
+    pub fn select_previous_revision(&mut self) {
+        self.history_state.select_previous();
+    }
+
+    // The position of the selected commit in `history`
+    fn selected_revision(&self) -> Option<usize> {
+        self.history_state
+            .selected()
+            .filter(|_| !self.history.is_empty())
+            .map(|i| i.min(self.history.len() - 1))
+    }
+
+    /// Shows the tasks as they were in the selected commit, to restore single tasks from.
+    pub fn open_revision(&mut self) {
+        let Some(revision) = self.selected_revision().map(|i| &self.history[i]) else {
+            return;
+        };
+        match self.storage.load_revision(revision) {
+            Ok(items) => {
+                self.history_tasks = items;
+                self.history_task_state
+                    .select((!self.history_tasks.is_empty()).then_some(0));
+                self.current_mode = Mode::HistoryTasks;
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not read the commit: {}", error));
+            }
+        }
+    }
+
+    pub fn close_revision(&mut self) {
+        self.current_mode = Mode::History;
+    }
+
+    pub fn select_next_in_revision(&mut self) {
+        self.history_task_state.select_next();
+    }
+
+    pub fn select_previous_in_revision(&mut self) {
+        self.history_task_state.select_previous();
+    }
+
+    /// Asks before replacing all tasks with those in the selected commit.
+    pub fn confirm_restore_revision(&mut self) {
+        if let Some(i) = self.selected_revision() {
+            self.pending_confirmation = Some(Confirmation::RestoreRevision(i));
+            self.current_mode = Mode::Confirming;
+        }
+    }
+
+    /// Brings back the task picked from a commit, replacing its current version or adding it
+    /// again if it was deleted since.
+    pub fn restore_task_from_revision(&mut self) {
+        if self.read_only {
+            self.status_message = Some("Read-only: changes are not saved".to_string());
+            return;
+        }
+        let Some(mut task) = self.history_task_state.selected().and_then(|i| {
+            self.history_tasks
+                .get(i.min(self.history_tasks.len().saturating_sub(1)))
+                .cloned()
+        }) else {
+            return;
+        };
+        // A subtask whose parent is gone is brought back at the top level
+        if task
+            .parent_id
+            .is_some_and(|parent| self.todo_list.get(parent).is_none())
+        {
+            task.parent_id = None;
+        }
+        let (id, name) = (task.id, task.todo.clone());
+        match self.todo_list.get_mut(id) {
+            Some(current) => *current = task,
+            None => self.todo_list.items.push(task),
+        }
+        self.todo_list.select_id(id);
+        self.status_message = Some(format!("Restored \"{}\"", name));
+        self.current_mode = Mode::TaskList;
+    }
+
+    // Replaces the tasks with those in a commit. The current tasks stay in the history, so a
+    // restore can itself be undone from it.
+    fn restore_revision(&mut self, i: usize) {
+        let Some(chosen) = self.history.get(i).cloned() else {
+            return;
+        };
+        if let Ok(false) = self.storage.lock() {
+            self.status_message = Some(
+                "Another TaskMaster is using the tasks; close it before restoring".to_string(),
+            );
+            return;
+        }
+        let result = self.storage.load_revision(&chosen).and_then(|items| {
+            self.todo_list.items = items;
+            self.storage.mark_seen();
+            self.read_only = false;
+            self.load_problem = None;
+            self.todo_list.state.select_first();
+            self.save()
+        });
+        self.status_message = Some(match result {
+            Ok(()) => format!(
+                "Restored the tasks as of {} ({})",
+                chosen.committed.format("%Y-%m-%d %H:%M:%S"),
+                chosen.short_id()
+            ),
+            Err(error) => format!("Could not restore the commit: {}", error),
+        });
+    }
+
+    /// Asks whether to bring back the tasks a crash left in a dump next to the main task file at
+    /// `main`. A problem loading the tasks is dealt with first; the dump is offered again on the
+    /// next start.
+    pub fn offer_dump(&mut self, main: PathBuf, dump: Dump) {
+        if self.pending_confirmation.is_some() {
+            self.status_message = Some(format!(
+                "Unsaved tasks from a crash are kept in {}",
+                emergency::dump_path(&main).display()
+            ));
+            return;
+        }
+        self.emergency_dump = Some((main, dump));
+        self.pending_confirmation = Some(Confirmation::RestoreDump);
+        self.current_mode = Mode::Confirming;
+    }
+
+    // Replaces the tasks of the list the dump came from with it, or throws it away
+    fn restore_dump(&mut self, accepted: bool) {
+        let Some((main, dump)) = self.emergency_dump.take() else {
+            return;
+        };
+        if accepted {
+            let written_at = dump.written_at;
+            self.switch_to_list(dump.list.clone());
+            if self.active_list != dump.list || self.read_only {
+                self.status_message = Some(format!(
+                    "Could not restore the tasks from the crash; they are kept in {}",
+                    emergency::dump_path(&main).display()
+                ));
+                return;
+            }
+            self.todo_list.items = dump.items;
+            self.todo_list.state.select_first();
+            if let Err(error) = self.save() {
+                self.status_message = Some(format!("Could not save: {}", error));
+                return;
+            }
+            self.status_message = Some(format!(
+                "Restored the tasks as they were at {}",
+                written_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+            ));
+        }
+        if let Err(error) = emergency::discard(&main) {
+            self.status_message = Some(format!("Could not remove the recovery file: {}", error));
+        }
+    }
+
+    /// Opens the popup listing the task lists to switch to.
+    pub fn open_lists(&mut self) {
+        self.moving_task = None;
+        self.show_list_choices();
+    }
+
+    /// Opens the popup listing the task lists to move the selected task to.
+    pub fn start_moving(&mut self) {
+        if self.read_only {
+            self.status_message = Some("Read-only: tasks can't be moved".to_string());
+            return;
+        }
+        if let Some(id) = self.todo_list.selected_id() {
+            self.moving_task = Some(id);
+            self.show_list_choices();
+        }
+    }
+
+    fn show_list_choices(&mut self) {
+        match self.storage.list_names() {
+            Ok(names) => {
+                self.list_choices = std::iter::once(None)
+                    .chain(names.into_iter().map(Some))
+                    .collect();
+                let active = self
+                    .list_choices
+                    .iter()
+                    .position(|list| *list == self.active_list);
+                self.list_state.select(Some(active.unwrap_or(0)));
+                self.current_mode = Mode::Lists;
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not list the task lists: {}", error));
+            }
+        }
+    }
+
+    pub fn close_lists(&mut self) {
+        self.moving_task = None;
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn select_next_list(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn select_previous_list(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    /// Switches to the selected list, or moves the task being moved there.
+    pub fn choose_selected_list(&mut self) {
+        let Some(i) = self.list_state.selected() else {
+            return;
+        };
+        if let Some(list) = self
+            .list_choices
+            .get(i.min(self.list_choices.len().saturating_sub(1)))
+        {
+            self.choose_list(list.clone());
+        }
+    }
+
+    fn choose_list(&mut self, list: Option<String>) {
+        match self.moving_task.take() {
+            Some(id) => {
+                self.current_mode = Mode::TaskList;
+                self.move_task_to_list(id, list);
+            }
+            None => self.switch_to_list(list),
+        }
+    }
+
+    /// Saves the tasks shown and loads the given list in their place, `None` being the main list.
+    pub fn switch_to_list(&mut self, list: Option<String>) {
+        self.current_mode = Mode::TaskList;
+        if list == self.active_list {
+            return;
+        }
+        if let Err(error) = self.save() {
+            self.status_message = Some(format!("Could not save: {}", error));
+            return;
+        }
+        match self
+            .storage
+            .open_list(list.as_deref())
+            .and_then(Self::load_or_default)
+        {
+            Ok(app) => {
+                let show_deferred = self.todo_list.show_deferred;
+                *self = Self {
+                    active_list: list,
+                    ..app
+                };
+                self.todo_list.show_deferred = show_deferred;
+                self.todo_list.state.select_first();
+                self.status_message.get_or_insert(format!(
+                    "Switched to {}",
+                    lists::label(self.active_list.as_deref())
+                ));
+            }
+            Err(error) => {
+                self.status_message = Some(format!("Could not open the list: {}", error));
+            }
+        }
+    }
+
+    /// Opens the prompt asking for the name of a new list.
+    pub fn start_naming_list(&mut self) {
+        self.prompt_input.clear();
+        self.current_mode = Mode::NamingList;
+    }
+
+    // Creates the list named in the prompt, then switches or moves the task to it like picking it
+    fn apply_new_list(&mut self) {
+        self.current_mode = Mode::Lists;
+        let name = std::mem::take(&mut self.prompt_input).trim().to_string();
+        if !lists::is_valid_name(&name) {
+            self.status_message = Some(format!(
+                "\"{}\" can't be used as a list name. Use letters, digits, spaces, '-' and '_'.",
+                name
+            ));
+            return;
+        }
+        if !self.list_choices.contains(&Some(name.clone())) {
+            let created = self
+                .storage
+                .open_list(Some(&name))
+                .and_then(|mut storage| storage.save(&[]));
+            if let Err(error) = created {
+                self.status_message = Some(format!("Could not create the list: {}", error));
+                return;
+            }
+        }
+        self.choose_list(Some(name));
+    }
+
+    // Moves a task with its subtasks to the end of another list. Dependency links between the
+    // moved tasks and the ones staying behind are dropped, as they can't point across lists.
+    fn move_task_to_list(&mut self, id: Uuid, list: Option<String>) {
+        if list == self.active_list {
+            return;
+        }
+        let mut moving = vec![id];
+        moving.extend(tree::descendants(&self.todo_list.items, id));
+        let mut moved: Vec<TodoItem> = self
+            .todo_list
+            .items
+            .iter()
+            .filter(|item| moving.contains(&item.id))
+            .cloned()
+            .collect();
+        for item in &mut moved {
+            if item.id == id {
+                item.parent_id = None;
+            }
+            item.depends_on
+                .retain(|prerequisite| moving.contains(prerequisite));
+        }
+
+        let label = lists::label(list.as_deref()).to_string();
+        let result = self
+            .storage
+            .open_list(list.as_deref())
+            .and_then(|mut storage| {
+                // Writing to the other list must not overwrite another TaskMaster's changes
+                if !storage.lock()? {
+                    return Err(io::Error::other(format!(
+                        "another TaskMaster is using {}",
+                        label
+                    )));
+                }
+                let mut items = match storage.load() {
+                    Ok(items) => items,
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+                    Err(error) => return Err(error),
+                };
+                items.extend(moved);
+                storage.save(&items)
+            });
+        if let Err(error) = result {
+            self.status_message = Some(format!("Could not move the task: {}", error));
+            return;
+        }
+
+        let title = self.todo_list.get(id).map(|item| item.todo.clone());
+        for item in &mut self.todo_list.items {
+            if !moving.contains(&item.id) {
+                item.depends_on
+                    .retain(|prerequisite| !moving.contains(prerequisite));
+            }
+        }
+        self.todo_list
+            .items
+            .retain(|item| !moving.contains(&item.id));
+        self.todo_list.state.select_first();
+        // The tasks are in the other list already, so they must not linger in this one
+        self.write_changes();
+        self.status_message = Some(match moving.len() - 1 {
+            0 => format!("Moved \"{}\" to {}", title.unwrap_or_default(), label),
+            1 => format!(
+                "Moved \"{}\" and 1 subtask to {}",
+                title.unwrap_or_default(),
+                label
+            ),
+            n => format!(
+                "Moved \"{}\" and {} subtasks to {}",
+                title.unwrap_or_default(),
+                n,
+                label
+            ),
+        });
+    }
+
+    /// Shows the open tasks of every list together.
+    pub fn open_all_lists(&mut self) {
+        let names = match self.storage.list_names() {
+            Ok(names) => names,
+            Err(error) => {
+                self.status_message = Some(format!("Could not list the task lists: {}", error));
+                return;
+            }
+        };
+        let mut all_tasks = Vec::new();
+        let mut unreadable = Vec::new();
+        for list in std::iter::once(None).chain(names.into_iter().map(Some)) {
+            let items = if list == self.active_list {
+                Ok(self.todo_list.items.clone())
+            } else {
+                match self
+                    .storage
+                    .open_list(list.as_deref())
+                    .and_then(|mut storage| storage.load())
+                {
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+                    result => result,
+                }
+            };
+            match items {
+                Ok(items) => all_tasks.extend(
+                    items
+                        .into_iter()
+                        .filter(|item| !item.status.is_closed())
+                        .map(|item| (list.clone(), item)),
+                ),
+                Err(_) => unreadable.push(lists::label(list.as_deref()).to_string()),
+            }
+        }
+        if !unreadable.is_empty() {
+            self.status_message = Some(format!("Could not read {}", unreadable.join(", ")));
+        }
+        self.all_tasks = all_tasks;
+        self.all_tasks_state
+            .select((!self.all_tasks.is_empty()).then_some(0));
+        self.moving_task = None;
+        self.current_mode = Mode::AllLists;
+    }
+
+    pub fn close_all_lists(&mut self) {
+        self.current_mode = Mode::TaskList;
+    }
+
+    pub fn select_next_in_all_lists(&mut self) {
+        self.all_tasks_state.select_next();
+    }
+
+    pub fn select_previous_in_all_lists(&mut self) {
+        self.all_tasks_state.select_previous();
+    }
+
+    /// Switches to the list of the task selected in the combined view and selects it there.
+    pub fn open_task_from_all_lists(&mut self) {
+        let Some((list, id)) = self
+            .all_tasks_state
+            .selected()
+            .and_then(|i| {
+                self.all_tasks
+                    .get(i.min(self.all_tasks.len().saturating_sub(1)))
+            })
+            .map(|(list, item)| (list.clone(), item.id))
+        else {
+            return;
+        };
+        self.switch_to_list(list);
+        self.todo_list.select_id(id);
+    }
+
+    /// Hides the subtasks of the selected task, or moves to its parent if they are already hidden.
+    pub fn collapse_selected(&mut self) {
+        if let Some(task) = self.todo_list.selected() {
+            let (id, parent_id) = (task.id, task.parent_id);
+            let has_children = self.todo_list.items.iter().any(|t| t.parent_id == Some(id));
+            if has_children && !task.collapsed {
+                if let Some(task) = self.todo_list.get_mut(id) {
+                    task.collapsed = true;
+                }
+            } else if let Some(parent_id) = parent_id {
+                self.todo_list.select_id(parent_id);
+            }
+        }
+    }
+
+    pub fn expand_selected(&mut self) {
+        if let Some(id) = self.todo_list.selected_id() {
+            if let Some(task) = self.todo_list.get_mut(id) {
+                task.collapsed = false;
+            }
+        }
+    }
+
+    pub fn indent_selected(&mut self) {
+        if let Some(id) = self.todo_list.selected_id() {
+            self.todo_list.indent(id);
+            self.todo_list.select_id(id);
+        }
+    }
+
+    pub fn outdent_selected(&mut self) {
+        if let Some(id) = self.todo_list.selected_id() {
+            self.todo_list.outdent(id);
+            self.todo_list.select_id(id);
+        }
+    }
+
+    /// Cancels the selected task, or reopens it if it is already cancelled.
+    pub fn toggle_cancelled(&mut self) {
+        if let Some((id, status)) = self.todo_list.selected().map(|t| (t.id, t.status)) {
+            self.todo_list.set_status(
+                id,
+                if status == Status::Cancelled {
+                    Status::Todo
+                } else {
+                    Status::Cancelled
+                },
+            );
         }
     }
 
@@ -363,6 +1956,7 @@ impl App {
         let todo_list = TodoList {
             items: test_items,
             state: ListState::default(),
+            show_deferred: false,
         };
 
         // Return the App with a test state
@@ -380,6 +1974,32 @@ impl App {
                 due_date_temp: None,
                 tags: Vec::new(),
             }),
+            pending_confirmation: None,
+            linking_task: None,
+            status_message: None,
+            prompt_input: String::new(),
+            time_log_state: ListState::default(),
+            editing_time_entry: None,
+            backups: Vec::new(),
+            backup_state: ListState::default(),
+            history: Vec::new(),
+            history_state: ListState::default(),
+            history_tasks: Vec::new(),
+            history_task_state: ListState::default(),
+            read_only: false,
+            load_problem: None,
+            saved_items: Vec::new(),
+            storage: Box::new(MemoryStorage::default()),
+            active_list: None,
+            list_choices: Vec::new(),
+            list_state: ListState::default(),
+            moving_task: None,
+            all_tasks: Vec::new(),
+            all_tasks_state: ListState::default(),
+            last_change: None,
+            save_error: None,
+            failed_writes: 0,
+            emergency_dump: None,
         }
     }
 }
@@ -0,0 +1,105 @@
+// The cli.rs file reads the command line.
+
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: taskmaster [--file <path>] [--global]
+       taskmaster init
+       taskmaster encrypt|decrypt [--file <path>] [--global]
+
+Commands:
+  init               Create a task file for the project in the current directory
+  encrypt            Encrypt the task file, its other lists and backups with a passphrase
+  decrypt            Turn an encrypted task file back into plain JSON
+
+Options:
+  -f, --file <path>  Use this task file instead of the configured one
+  -g, --global       Use your personal tasks even inside a project
+  -h, --help         Show this help";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub command: Option<Command>,
+    pub file: Option<PathBuf>,
+    // Skip looking for a project task file
+    pub global: bool,
+    pub help: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Creates `.taskmaster/tasks.json` in the current directory.
+    Init,
+    /// Encrypts the tasks that would be opened.
+    Encrypt,
+    /// Decrypts the tasks that would be opened.
+    Decrypt,
+}
+
+impl Args {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-f" | "--file" => {
+                    let path = args.next().ok_or_else(|| format!("{} needs a path", arg))?;
+                    parsed.file = Some(PathBuf::from(path));
+                }
+                "-g" | "--global" => parsed.global = true,
+                "-h" | "--help" => parsed.help = true,
+                "init" if parsed.command.is_none() => parsed.command = Some(Command::Init),
+                "encrypt" if parsed.command.is_none() => parsed.command = Some(Command::Encrypt),
+                "decrypt" if parsed.command.is_none() => parsed.command = Some(Command::Decrypt),
+                _ => match arg.strip_prefix("--file=") {
+                    Some(path) => parsed.file = Some(PathBuf::from(path)),
+                    None => return Err(format!("Unknown argument '{}'", arg)),
+                },
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let expected = Some(PathBuf::from("work.json"));
+        assert_eq!(parse(&["--file", "work.json"]).unwrap().file, expected);
+        assert_eq!(parse(&["-f", "work.json"]).unwrap().file, expected);
+        assert_eq!(parse(&["--file=work.json"]).unwrap().file, expected);
+        assert_eq!(parse(&[]).unwrap(), Args::default());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_arguments() {
+        assert!(parse(&["--file"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["-h"]).unwrap().help);
+        assert!(parse(&["init", "init"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_init() {
+        let args = parse(&["init", "--global"]).unwrap();
+        assert_eq!(args.command, Some(Command::Init));
+        assert!(args.global);
+    }
+
+    #[test]
+    fn test_parse_encryption_commands() {
+        let args = parse(&["encrypt", "-f", "work.json"]).unwrap();
+        assert_eq!(args.command, Some(Command::Encrypt));
+        assert_eq!(args.file, Some(PathBuf::from("work.json")));
+        assert_eq!(parse(&["decrypt"]).unwrap().command, Some(Command::Decrypt));
+        assert!(parse(&["encrypt", "decrypt"]).is_err());
+    }
+}
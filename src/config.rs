@@ -0,0 +1,57 @@
+// The config.rs file reads the optional settings file, `config.json` in the platform's config
+// directory (e.g. `~/.config/taskmaster/config.json` on Linux).
+
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// The task file to use. A relative path is taken relative to the config file.
+    pub file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Reads the config file, or returns the defaults if there is none.
+    pub fn load() -> io::Result<Self> {
+        match config_path() {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        let mut config: Self = serde_json::from_str(&contents).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Could not read the config file {}: {}",
+                    path.display(),
+                    error
+                ),
+            )
+        })?;
+        if let (Some(file), Some(dir)) = (&config.file, path.parent()) {
+            config.file = Some(dir.join(file));
+        }
+        Ok(config)
+    }
+}
+
+/// The platform's directories for TaskMaster, or `None` if there is no home directory to put
+/// them in.
+pub fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "", "taskmaster")
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().join("config.json"))
+}
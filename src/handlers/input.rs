@@ -1,25 +1,60 @@
-use crate::app::state::Mode;
+use crate::app::state::{Confirmation, Mode};
 use crate::app::App;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::Instant;
 
 pub fn handle_key(app: &mut App, key: KeyEvent) {
+    // Feedback from the previous action is dismissed by the next key press
+    app.status_message = None;
+
     match app.current_mode {
         Mode::TaskList => handle_task_list_input(app, key),
         Mode::Editing => handle_editing_input(app, key),
         Mode::Creating => handle_creation_input(app, key),
+        Mode::Confirming => handle_confirmation_input(app, key),
+        Mode::Linking => handle_linking_input(app, key),
+        Mode::Deferring | Mode::EditingTimeEntry | Mode::NamingList => {
+            handle_prompt_input(app, key)
+        }
+        Mode::TimeLog => handle_time_log_input(app, key),
+        Mode::Backups => handle_backups_input(app, key),
+        Mode::Lists => handle_lists_input(app, key),
+        Mode::AllLists => handle_all_lists_input(app, key),
+        Mode::History => handle_history_input(app, key),
+        Mode::HistoryTasks => handle_history_tasks_input(app, key),
     }
+
+    // Whatever the key changed is stored once the edits calm down
+    app.note_changes(Instant::now());
 }
 
 fn handle_task_list_input(app: &mut App, key: KeyEvent) {
     match key.code {
         KeyCode::Char('n') => app.create_new_task(),
+        KeyCode::Char('a') => app.create_new_subtask(),
         KeyCode::Char('e') => app.enter_editing_mode(),
         KeyCode::Char('q') | KeyCode::Esc => app.should_exit = true,
         KeyCode::Up => app.select_previous(),
         KeyCode::Down => app.select_next(),
         KeyCode::Char('g') => app.select_first(),
         KeyCode::Char('G') => app.select_last(),
+        KeyCode::Left => app.collapse_selected(),
+        KeyCode::Right => app.expand_selected(),
+        KeyCode::Tab => app.indent_selected(),
+        KeyCode::BackTab => app.outdent_selected(),
+        KeyCode::Char('s') => app.sort_by_priority(),
         KeyCode::Char(' ') | KeyCode::Char('l') | KeyCode::Enter => app.toggle_status(),
+        KeyCode::Char('t') => app.cycle_status(),
+        KeyCode::Char('x') => app.toggle_cancelled(),
+        KeyCode::Char('d') => app.start_linking(),
+        KeyCode::Char('z') => app.start_deferring(),
+        KeyCode::Char('v') => app.toggle_show_deferred(),
+        KeyCode::Char('r') => app.toggle_timer(),
+        KeyCode::Char('L') => app.open_time_log(),
+        KeyCode::Char('B') => app.open_backups(),
+        KeyCode::Char('H') => app.open_history(),
+        KeyCode::Char('o') => app.open_lists(),
+        KeyCode::Char('m') => app.start_moving(),
         KeyCode::Delete if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.delete_selected_task()
         }
@@ -72,3 +107,112 @@ fn handle_creation_input(app: &mut App, key: KeyEvent) {
         }
     }
 }
+
+fn handle_confirmation_input(app: &mut App, key: KeyEvent) {
+    if app.pending_confirmation == Some(Confirmation::ExternalChange) {
+        return handle_external_change_input(app, key);
+    }
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => app.answer_confirmation(true),
+        KeyCode::Char('n') | KeyCode::Char('N') => app.answer_confirmation(false),
+        KeyCode::Esc => app.cancel_confirmation(),
+        _ => {}
+    }
+}
+
+fn handle_external_change_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('r') => app.reload_from_disk(),
+        KeyCode::Char('m') => app.merge_from_disk(),
+        KeyCode::Char('k') | KeyCode::Esc => app.keep_own_changes(),
+        _ => {}
+    }
+}
+
+fn handle_linking_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous(),
+        KeyCode::Down => app.select_next(),
+        KeyCode::Char('g') => app.select_first(),
+        KeyCode::Char('G') => app.select_last(),
+        KeyCode::Enter | KeyCode::Char(' ') => app.toggle_dependency(),
+        KeyCode::Esc => app.cancel_linking(),
+        _ => {}
+    }
+}
+
+fn handle_prompt_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => app.submit_prompt(),
+        KeyCode::Esc => app.cancel_prompt(),
+        KeyCode::Char(c) => app.prompt_input(c),
+        KeyCode::Backspace => app.backspace_prompt_input(),
+        _ => {}
+    }
+}
+
+fn handle_time_log_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_time_entry(),
+        KeyCode::Down => app.select_next_time_entry(),
+        KeyCode::Char('e') | KeyCode::Enter => app.start_editing_time_entry(false),
+        KeyCode::Char('a') => app.start_editing_time_entry(true),
+        KeyCode::Char('x') | KeyCode::Delete => app.delete_time_entry(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_time_log(),
+        _ => {}
+    }
+}
+
+fn handle_backups_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_backup(),
+        KeyCode::Down => app.select_next_backup(),
+        KeyCode::Enter => app.confirm_restore_backup(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_backups(),
+        _ => {}
+    }
+}
+
+fn handle_history_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_revision(),
+        KeyCode::Down => app.select_next_revision(),
+        KeyCode::Enter => app.open_revision(),
+        KeyCode::Char('r') => app.confirm_restore_revision(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_history(),
+        _ => {}
+    }
+}
+
+fn handle_history_tasks_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_in_revision(),
+        KeyCode::Down => app.select_next_in_revision(),
+        KeyCode::Enter => app.restore_task_from_revision(),
+        KeyCode::Char('r') => app.confirm_restore_revision(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_revision(),
+        _ => {}
+    }
+}
+
+fn handle_lists_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_list(),
+        KeyCode::Down => app.select_next_list(),
+        KeyCode::Enter => app.choose_selected_list(),
+        KeyCode::Char('n') => app.start_naming_list(),
+        KeyCode::Char('a') if app.moving_task.is_none() => app.open_all_lists(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_lists(),
+        _ => {}
+    }
+}
+
+fn handle_all_lists_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up => app.select_previous_in_all_lists(),
+        KeyCode::Down => app.select_next_in_all_lists(),
+        KeyCode::Enter => app.open_task_from_all_lists(),
+        KeyCode::Esc | KeyCode::Char('q') => app.close_all_lists(),
+        _ => {}
+    }
+}
@@ -5,9 +5,39 @@ pub use input::handle_key;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::state::Mode;
+    use crate::app::state::{Mode, AUTOSAVE_DELAY};
     use crate::app::App;
+    use crate::models::Status;
+    use crate::models::TodoItem;
+    use crate::storage::emergency::{self, Dump};
+    use crate::storage::git::GitStorage;
+    use crate::storage::{JsonFileStorage, Storage};
+    use chrono::Utc;
     use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::io;
+    use std::rc::Rc;
+
+    // Presses a key without modifiers
+    fn press(app: &mut App, code: KeyCode) {
+        handle_key(app, KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    // Storage that can no longer be written, like a full disk
+    struct FailingStorage;
+
+    impl Storage for FailingStorage {
+        fn describe(&self) -> String {
+            "failing".to_string()
+        }
+
+        fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+            Ok(Vec::new())
+        }
+
+        fn save(&mut self, _items: &[TodoItem]) -> io::Result<()> {
+            Err(io::Error::other("No space left on device"))
+        }
+    }
 
     #[test]
     fn test_handle_key_task_list_mode() {
@@ -73,4 +103,520 @@ mod tests {
             "Expected selection to remain at the last task"
         );
     }
+
+    #[test]
+    fn test_completing_parent_asks_about_open_subtasks() {
+        let mut app = App::load_test_data();
+        app.todo_list.state.select(Some(1));
+
+        // Indent "Test Task 2" below "Test Task 1"
+        handle_key(&mut app, KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        let parent_id = app.todo_list.items[0].id;
+        assert_eq!(app.todo_list.items[1].parent_id, Some(parent_id));
+
+        // Completing the parent asks first
+        app.todo_list.state.select(Some(0));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.current_mode, Mode::Confirming);
+        assert_eq!(app.todo_list.items[0].status, Status::Todo);
+
+        // Accepting completes the subtask as well
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.items[0].status, Status::Completed);
+        assert_eq!(app.todo_list.items[1].status, Status::Completed);
+
+        // Outdenting moves the subtask back to the top level
+        app.todo_list.state.select(Some(1));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::BackTab, KeyModifiers::NONE),
+        );
+        assert_eq!(app.todo_list.items[1].parent_id, None);
+    }
+
+    #[test]
+    fn test_declining_completes_only_the_parent() {
+        let mut app = App::load_test_data();
+        app.todo_list.state.select(Some(1));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+
+        app.todo_list.state.select(Some(0));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.todo_list.items[0].status, Status::Completed);
+        assert_eq!(app.todo_list.items[1].status, Status::Todo);
+    }
+
+    #[test]
+    fn test_linking_refuses_cycles() {
+        let mut app = App::load_test_data();
+        let (first, second) = (app.todo_list.items[0].id, app.todo_list.items[1].id);
+
+        // "Test Task 1" waits for "Test Task 2"
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::Linking);
+        handle_key(&mut app, KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.items[0].depends_on, vec![second]);
+        assert_eq!(app.todo_list.selected_id(), Some(first));
+
+        // The reverse link would be a cycle
+        app.todo_list.state.select(Some(1));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE),
+        );
+        handle_key(&mut app, KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.todo_list.items[1].depends_on.is_empty());
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_completing_recurring_task_adds_next_occurrence() {
+        let mut app = App::load_test_data();
+        app.todo_list.items[0].recurrence = crate::models::Recurrence::parse("daily");
+        app.todo_list.state.select(Some(0));
+
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(app.todo_list.items.len(), 4);
+        assert_eq!(app.todo_list.items[0].status, Status::Completed);
+        assert_eq!(app.todo_list.items[1].todo, "Test Task 1");
+        assert_eq!(app.todo_list.items[1].status, Status::Todo);
+        assert!(app.todo_list.items[1].due_date.is_some());
+
+        // Reopening and completing again does not add another one
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.todo_list.items.len(), 4);
+    }
+
+    #[test]
+    fn test_deferring_hides_task_until_revealed() {
+        let mut app = App::load_test_data();
+        let deferred_id = app.todo_list.items[0].id;
+        app.todo_list.state.select(Some(0));
+
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::Deferring);
+        for c in "3d".chars() {
+            handle_key(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            );
+        }
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(app.current_mode, Mode::TaskList);
+        let today = chrono::Local::now().date_naive();
+        assert_eq!(
+            app.todo_list.items[0].scheduled,
+            today.checked_add_days(chrono::Days::new(3))
+        );
+        assert_eq!(app.todo_list.rows().len(), 2);
+
+        // Revealing deferred tasks lists it again
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.todo_list.rows().len(), 3);
+
+        // "now" clears the scheduled date
+        app.todo_list.select_id(deferred_id);
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE),
+        );
+        for c in "now".chars() {
+            handle_key(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            );
+        }
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(app.todo_list.items[0].scheduled.is_none());
+    }
+
+    #[test]
+    fn test_only_one_timer_runs() {
+        let mut app = App::load_test_data();
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE),
+        );
+        assert!(app.todo_list.items[0].is_timer_running());
+
+        app.todo_list.state.select(Some(1));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE),
+        );
+        assert!(!app.todo_list.items[0].is_timer_running());
+        assert!(app.todo_list.items[1].is_timer_running());
+
+        // Completing the task stops its timer
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(!app.todo_list.items[1].is_timer_running());
+    }
+
+    #[test]
+    fn test_editing_time_log() {
+        let mut app = App::load_test_data();
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('L'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::TimeLog);
+
+        // Add an interval by replacing the suggested one
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::EditingTimeEntry);
+        app.prompt_input.clear();
+        for c in "2026-10-18 09:00 - 10:30".chars() {
+            handle_key(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            );
+        }
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.current_mode, Mode::TimeLog);
+        assert_eq!(app.todo_list.items[0].time_log.len(), 1);
+
+        // Delete it again
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+        );
+        assert!(app.todo_list.items[0].time_log.is_empty());
+        handle_key(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        assert_eq!(app.current_mode, Mode::TaskList);
+    }
+
+    #[test]
+    fn test_estimate_from_edit_form() {
+        let mut app = App::load_test_data();
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE),
+        );
+        // Task name, description, due date, then the estimate
+        for _ in 0..3 {
+            handle_key(&mut app, KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE));
+        }
+        for c in "1h30m".chars() {
+            handle_key(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            );
+        }
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+        );
+
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.items[0].estimate_minutes, Some(90));
+        assert_eq!(app.todo_list.estimate_summary(), (90, 1));
+
+        // Closed tasks drop out of the summary
+        handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(app.todo_list.estimate_summary(), (0, 0));
+
+        // Huge estimates from a hand-edited file don't overflow the total
+        for item in &mut app.todo_list.items {
+            item.status = Status::Todo;
+            item.estimate_minutes = Some(u32::MAX);
+        }
+        let count = app.todo_list.items.len();
+        assert_eq!(app.todo_list.estimate_summary(), (u32::MAX, count));
+    }
+
+    #[test]
+    fn test_damaged_file_opens_read_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let contents = r#"{"schema_version":1,"items":[{"todo":"Kept","info":"","status":"Todo","due_date":null,"tags":[]},{"todo":"#;
+        std::fs::write(&file_path, contents).unwrap();
+        let error = crate::storage::file::load_from_file(&file_path).unwrap_err();
+
+        let storage = crate::storage::JsonFileStorage::new(file_path.clone());
+        let mut app = App::recover(Box::new(storage), error);
+
+        // The complete item is offered, and the damaged file stays until it is accepted
+        assert!(file_path.exists());
+        assert_eq!(app.current_mode, Mode::Confirming);
+        assert!(app.read_only);
+        assert_eq!(app.todo_list.items.len(), 1);
+        assert_eq!(app.todo_list.items[0].todo, "Kept");
+
+        // Declining keeps browsing read-only
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE),
+        );
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert!(app.read_only);
+        assert!(app.load_problem.is_some());
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_accepting_salvaged_tasks_moves_damaged_file_aside() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let contents = r#"{"schema_version":1,"items":[{"todo":"Kept","info":"","status":"Todo","due_date":null,"tags":[]},{"todo":"#;
+        std::fs::write(&file_path, contents).unwrap();
+        let error = crate::storage::file::load_from_file(&file_path).unwrap_err();
+        let storage = crate::storage::JsonFileStorage::new(file_path.clone());
+        let mut app = App::recover(Box::new(storage), error);
+
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+        );
+
+        // The salvaged tasks take the place of the damaged file, which is kept next to it
+        assert!(!app.read_only);
+        let saved = crate::storage::file::load_from_file(&file_path).unwrap();
+        assert_eq!(saved.items.len(), 1);
+        assert_eq!(saved.items[0].todo, "Kept");
+        let quarantined: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().contains(".corrupt-"))
+            .collect();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(std::fs::read_to_string(&quarantined[0]).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_changes_are_written_to_storage() {
+        let storage = crate::storage::MemoryStorage::default();
+        let mut app = App::load_or_default(Box::new(storage)).unwrap();
+        let demo_tasks = app.todo_list.items.len();
+
+        // The first write stores the whole list, as there is nothing to update yet
+        app.create_new_task();
+        for c in "Stored".chars() {
+            handle_key(
+                &mut app,
+                KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE),
+            );
+        }
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+        );
+        assert!(app.has_unsaved_changes());
+        let changed_at = app.last_change.unwrap();
+        app.autosave(changed_at + AUTOSAVE_DELAY);
+        assert!(!app.has_unsaved_changes());
+        assert_eq!(app.storage.load().unwrap().len(), demo_tasks + 1);
+
+        // Later changes update single tasks, once the edits calm down
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Delete, KeyModifiers::CONTROL),
+        );
+        let changed_at = app.last_change.unwrap();
+        app.autosave(changed_at);
+        assert_eq!(app.storage.load().unwrap().len(), demo_tasks + 1);
+        app.autosave(changed_at + AUTOSAVE_DELAY);
+        let stored = app.storage.load().unwrap();
+        assert_eq!(stored.len(), demo_tasks);
+        assert!(stored.iter().all(|item| item.todo != "Stored"));
+
+        // Including ones that used to wait for the app to exit
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE),
+        );
+        app.autosave(app.last_change.unwrap() + AUTOSAVE_DELAY);
+        let selected = app.todo_list.selected().unwrap();
+        let stored = app.storage.load().unwrap();
+        let stored = stored.iter().find(|item| item.id == selected.id).unwrap();
+        assert_eq!(stored.status, selected.status);
+    }
+
+    #[test]
+    fn test_moving_tasks_between_lists() {
+        let mut app = App::load_test_data();
+        app.save().unwrap();
+
+        // Move the first task to a new list
+        app.todo_list.state.select(Some(0));
+        press(&mut app, KeyCode::Char('m'));
+        assert_eq!(app.current_mode, Mode::Lists);
+        press(&mut app, KeyCode::Char('n'));
+        for c in "Work".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.items.len(), 2);
+        assert_eq!(app.storage.load().unwrap().len(), 2);
+
+        // Switch to it
+        press(&mut app, KeyCode::Char('o'));
+        assert_eq!(app.list_choices, [None, Some("Work".to_string())]);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.active_list.as_deref(), Some("Work"));
+        assert_eq!(app.todo_list.items.len(), 1);
+        assert_eq!(app.todo_list.items[0].todo, "Test Task 1");
+
+        // The combined view shows the open tasks of both lists and leads back to the main one
+        press(&mut app, KeyCode::Char('o'));
+        press(&mut app, KeyCode::Char('a'));
+        assert_eq!(app.current_mode, Mode::AllLists);
+        assert_eq!(app.all_tasks.len(), 2);
+        assert_eq!(app.all_tasks[0].1.todo, "Test Task 2");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.active_list, None);
+        assert_eq!(app.todo_list.selected().unwrap().todo, "Test Task 2");
+    }
+
+    #[test]
+    fn test_failed_autosave_is_reported() {
+        let mut app = App::load_test_data();
+        app.save().unwrap();
+        app.storage = Box::new(FailingStorage);
+
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+        );
+        app.autosave(app.last_change.unwrap() + AUTOSAVE_DELAY);
+
+        assert!(app.save_error.is_some());
+        assert!(app.has_unsaved_changes());
+    }
+
+    #[test]
+    fn test_failed_autosave_is_retried() {
+        let mut app = App::load_test_data();
+        app.save().unwrap();
+        app.storage = Box::new(FailingStorage);
+        app.todo_list.state.select(Some(0));
+        handle_key(
+            &mut app,
+            KeyEvent::new(KeyCode::Char(' '), KeyModifiers::NONE),
+        );
+        let failed_at = app.last_change.unwrap() + AUTOSAVE_DELAY;
+        app.autosave(failed_at);
+        assert!(app.save_error.is_some());
+
+        // The storage recovers; the next try waits a little longer and then writes
+        app.storage = Box::new(crate::storage::MemoryStorage::default());
+        app.autosave(failed_at + AUTOSAVE_DELAY);
+        assert!(app.has_unsaved_changes());
+        app.autosave(failed_at + 2 * AUTOSAVE_DELAY);
+        assert!(!app.has_unsaved_changes());
+        assert!(app.save_error.is_none());
+        assert_eq!(app.storage.load().unwrap(), app.todo_list.items);
+    }
+
+    #[test]
+    fn test_tasks_from_a_crash_can_be_restored() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main = temp_dir.path().join("tasks.json");
+        let dump = || Dump {
+            list: None,
+            written_at: Utc::now(),
+            items: vec![TodoItem::new(Status::Todo, "Unsaved", "")],
+        };
+
+        // Declining throws the dump away
+        emergency::write(&main, &dump(), None).unwrap();
+        let mut app = App::load_test_data();
+        app.offer_dump(main.clone(), emergency::read(&main, None).unwrap().unwrap());
+        assert_eq!(app.current_mode, Mode::Confirming);
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.todo_list.items.len(), 3);
+        assert!(emergency::read(&main, None).unwrap().is_none());
+
+        // Accepting brings the tasks back and stores them
+        emergency::write(&main, &dump(), None).unwrap();
+        app.offer_dump(main.clone(), emergency::read(&main, None).unwrap().unwrap());
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.items.len(), 1);
+        assert_eq!(app.storage.load().unwrap()[0].todo, "Unsaved");
+        assert!(!app.has_unsaved_changes());
+        assert!(emergency::read(&main, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_restoring_from_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(temp_dir.path())
+            .status()
+            .unwrap();
+        let mut app = App::load_test_data();
+        app.storage = Box::new(
+            GitStorage::open(
+                Box::new(JsonFileStorage::new(temp_dir.path().join("tasks.json"))),
+                temp_dir.path().to_path_buf(),
+                Rc::new(|path| Ok(Box::new(JsonFileStorage::new(path)))),
+            )
+            .unwrap(),
+        );
+        app.save().unwrap();
+        let original = app.todo_list.items.clone();
+
+        // Complete a task, which is committed once saved
+        let id = original[0].id;
+        app.todo_list.select_id(id);
+        press(&mut app, KeyCode::Char(' '));
+        app.autosave(app.last_change.unwrap() + AUTOSAVE_DELAY);
+        press(&mut app, KeyCode::Char('H'));
+        assert_eq!(app.current_mode, Mode::History);
+        assert_eq!(app.history.len(), 2);
+        assert_eq!(app.history[0].message, "complete: Test Task 1");
+
+        // Bring back the task as it was in the first commit
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_mode, Mode::HistoryTasks);
+        assert_eq!(app.history_tasks, original);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.current_mode, Mode::TaskList);
+        assert_eq!(app.todo_list.get(id).unwrap().status, Status::Todo);
+
+        // Or the whole list
+        app.todo_list.items.clear();
+        press(&mut app, KeyCode::Char('H'));
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.todo_list.items, original);
+    }
 }
@@ -1,4 +1,6 @@
 mod app;
+mod cli;
+mod config;
 mod handlers;
 mod models;
 mod storage;
@@ -6,47 +8,295 @@ mod tui;
 mod ui;
 
 use app::App;
-use crossterm::event::{self, Event};
+use chrono::Utc;
+use cli::{Args, Command};
+use config::Config;
+use crossterm::event::{self, Event, KeyCode};
 use handlers::handle_key;
+use ratatui::{backend::Backend, Terminal};
 use std::error::Error;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
+use storage::crypto::{self, Key};
+use storage::emergency::{self, Dump};
+use storage::{encrypted, location, lock, open_storage};
+use ui::components::PassphrasePrompt;
 
 fn main() -> Result<(), Box<dyn Error>> {
     tui::init_error_hooks()?;
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    if args.command == Some(Command::Init) {
+        let created = std::env::current_dir()
+            .and_then(|dir| location::init_project(&dir))
+            .and_then(|path| {
+                open_storage(path.clone(), None)?.save(&[])?;
+                Ok(path)
+            });
+        match created {
+            Ok(path) => println!("Created {}", path.display()),
+            Err(error) => {
+                eprintln!("Could not create the project task file: {}", error);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // Inside a project, its tasks are used instead of the personal ones
+    let project = std::env::current_dir()
+        .ok()
+        .filter(|_| !args.global)
+        .and_then(|dir| location::find_project_file(&dir, location::home_dir().as_deref()));
+
+    // Load before taking over the terminal so a refusal to open the task file stays readable
+    let (path, moved_from) =
+        match Config::load().and_then(|config| location::resolve(args.file, project, &config)) {
+            Ok(found) => found,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        };
+    if let Some(command @ (Command::Encrypt | Command::Decrypt)) = args.command {
+        match change_encryption(&path, command == Command::Encrypt) {
+            Ok(done) => println!("{}", done),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // An encrypted task file can only be read once its passphrase is given
+    let key = if crypto::is_encrypted(&path) {
+        let mut prompt = PassphrasePrompt::new(
+            "Encrypted tasks",
+            format!("Enter the passphrase for {}", path.display()),
+        );
+        match ask_passphrase(&mut prompt, |passphrase| crypto::unlock(&path, passphrase))? {
+            Some(key) => Some(key),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+
+    let mut app = match open_storage(path.clone(), key.clone()).and_then(App::load_or_default) {
+        Ok(app) => app,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+    if let Some(legacy) = moved_from {
+        app.status_message.get_or_insert(format!(
+            "Moved your tasks from {} to {}",
+            legacy.display(),
+            path.parent().unwrap_or(&path).display()
+        ));
+    }
+    // A crash last time may have left tasks that never reached the task file
+    match emergency::read(&path, key.as_ref()) {
+        Ok(Some(dump)) => app.offer_dump(path.clone(), dump),
+        Ok(None) => {}
+        Err(error) => {
+            app.status_message = Some(format!("Could not read the recovery file: {}", error));
+        }
+    }
+
+    let shutdown = tui::init_shutdown_signals()?;
     let mut terminal = tui::init_terminal()?; // Make the terminal variable mutable
 
-    let mut app = App::load_or_default();
+    // On a panic the terminal is restored by the panic hook; what's left is to keep the tasks
+    let outcome = match panic::catch_unwind(AssertUnwindSafe(|| {
+        run(&mut terminal, &mut app, &shutdown)
+    })) {
+        Ok(outcome) => outcome,
+        Err(panic) => {
+            if app.has_unsaved_changes() {
+                let dump = Dump {
+                    list: app.active_list.clone(),
+                    written_at: Utc::now(),
+                    items: app.todo_list.items.clone(),
+                };
+                match emergency::write(&path, &dump, key.as_ref()) {
+                    Ok(dump_path) => eprintln!(
+                        "Your unsaved tasks were written to {}. TaskMaster will offer to restore them on the next start.",
+                        dump_path.display()
+                    ),
+                    Err(error) => eprintln!("Could not keep your unsaved tasks: {}", error),
+                }
+            }
+            panic::resume_unwind(panic);
+        }
+    };
+
+    // Saves tasks before exiting, also when the loop ended in an error or on a signal
+    let saved = app.save();
+    let restored = tui::restore_terminal();
+    outcome?;
+    saved?;
+    // After a hangup there is no terminal left to restore
+    if !shutdown.load(Ordering::Relaxed) {
+        restored?;
+    }
+    Ok(())
+}
+
+// Runs the application loop until the user quits or the process is asked to stop
+fn run(
+    terminal: &mut Terminal<impl Backend>,
+    app: &mut App,
+    shutdown: &AtomicBool,
+) -> io::Result<()> {
     let blink_interval = Duration::from_millis(500); // Blink every 500ms
     let mut last_blink = Instant::now();
 
     // Run the application loop with cursor blinking
     loop {
-        // Handle the cursor blinking independently of keypresses
+        // Handle the cursor blinking independently of keypresses. The redraw on each tick also
+        // advances the elapsed time shown for a running timer.
         if last_blink.elapsed() >= blink_interval {
             app.toggle_cursor_visibility(); // Toggle cursor visibility
+            app.check_for_external_changes(); // Notice when another process saved the task file
             last_blink = Instant::now();
-            terminal.draw(|f| f.render_widget(&mut app, f.area()))?; // Redraw UI to update cursor
+            terminal.draw(|f| f.render_widget(&mut *app, f.area()))?; // Redraw UI to update cursor
         }
 
         // Non-blocking event handling
         if event::poll(Duration::from_millis(10))? {
             if let Event::Key(key) = event::read()? {
-                handle_key(&mut app, key);
+                handle_key(app, key);
 
                 // Redraw UI after a key event
-                terminal.draw(|f| f.render_widget(&mut app, f.area()))?;
+                terminal.draw(|f| f.render_widget(&mut *app, f.area()))?;
             }
         }
 
-        // Exit the loop if the app signals to exit
-        if app.should_exit {
-            break;
+        // Store the changes once the user pauses
+        app.autosave(Instant::now());
+
+        // Exit the loop if the app signals to exit, or the process is told to stop
+        if app.should_exit || shutdown.load(Ordering::Relaxed) {
+            return Ok(());
         }
     }
+}
 
-    // Saves tasks before exiting
-    app.save()?;
-
+// Shows the passphrase prompt until `check` accepts what was typed, and returns what it made of
+// it. Returns `None` if the user gave up.
+fn ask_passphrase<T>(
+    prompt: &mut PassphrasePrompt,
+    mut check: impl FnMut(&str) -> io::Result<T>,
+) -> io::Result<Option<T>> {
+    let mut terminal = tui::init_terminal()?;
+    let answer = loop {
+        terminal.draw(|f| f.render_widget(&*prompt, f.area()))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match key.code {
+            KeyCode::Esc => break None,
+            KeyCode::Enter => {
+                // Deriving the key takes a moment
+                prompt.problem = Some("Checking…".to_string());
+                terminal.draw(|f| f.render_widget(&*prompt, f.area()))?;
+                match check(&prompt.input) {
+                    Ok(answer) => break Some(answer),
+                    Err(error) => {
+                        prompt.problem = Some(error.to_string());
+                        prompt.input.clear();
+                    }
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Char(c) => prompt.input.push(c),
+            _ => {}
+        }
+    };
     tui::restore_terminal()?;
-    Ok(())
+    Ok(answer)
+}
+
+// Encrypts or decrypts the task file at `path` with its other lists and backups, and says what was
+// done
+fn change_encryption(path: &Path, encrypt: bool) -> io::Result<String> {
+    if std::env::var("TASKMASTER_BACKEND").is_ok_and(|backend| backend != "json") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Only task files of the JSON backend can be encrypted",
+        ));
+    }
+    if crypto::is_encrypted(path) == encrypt {
+        let state = if encrypt { "already" } else { "not" };
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is {} encrypted", path.display(), state),
+        ));
+    }
+    // Held until the change is done, so a running TaskMaster can't write in between
+    let Some(_lock) = lock::try_lock(path)? else {
+        return Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            "Another TaskMaster is using the tasks; close it first",
+        ));
+    };
+
+    if !encrypt {
+        let mut prompt = PassphrasePrompt::new(
+            "Decrypt tasks",
+            format!("Enter the passphrase for {}", path.display()),
+        );
+        let Some(key) = ask_passphrase(&mut prompt, |passphrase| crypto::unlock(path, passphrase))?
+        else {
+            return Ok("Nothing was changed".to_string());
+        };
+        encrypted::decrypt_store(path, &key)?;
+        return Ok(format!("Decrypted {}", path.display()));
+    }
+
+    let mut prompt = PassphrasePrompt::new(
+        "Encrypt tasks",
+        format!("Choose a passphrase for {}", path.display()),
+    );
+    let Some(passphrase) = ask_passphrase(&mut prompt, |passphrase| match passphrase {
+        "" => Err(io::Error::other("The passphrase can't be empty")),
+        _ => Ok(passphrase.to_string()),
+    })?
+    else {
+        return Ok("Nothing was changed".to_string());
+    };
+    let mut prompt = PassphrasePrompt::new("Encrypt tasks", "Repeat the passphrase".to_string());
+    let Some(key) = ask_passphrase(&mut prompt, |repeated| match repeated == passphrase {
+        true => Key::generate(&passphrase),
+        false => Err(io::Error::other("The passphrases don't match")),
+    })?
+    else {
+        return Ok("Nothing was changed".to_string());
+    };
+    let copies = encrypted::encrypt_store(path, &key)?;
+    let mut message = format!("Encrypted {}", path.display());
+    for copy in copies {
+        message.push_str(&format!("\nEncrypted {}", copy.display()));
+    }
+    message.push_str("\nOlder versions in a git history stay readable.");
+    Ok(message)
 }
@@ -0,0 +1,40 @@
+// The dependencies.rs file answers questions about "can't start X until Y is done" links. Each task
+// stores the IDs of its prerequisites in `depends_on`, so links survive reordering and reloads.
+
+use crate::models::TodoItem;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Whether making `task` depend on `prerequisite` would close a loop, i.e. `prerequisite` already
+/// depends on `task` directly or through other tasks.
+pub fn would_create_cycle(items: &[TodoItem], task: Uuid, prerequisite: Uuid) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![prerequisite];
+    while let Some(id) = stack.pop() {
+        if id == task {
+            return true;
+        }
+        if seen.insert(id) {
+            if let Some(item) = items.iter().find(|item| item.id == id) {
+                stack.extend(&item.depends_on);
+            }
+        }
+    }
+    false
+}
+
+/// The prerequisites of a task that are still open. Links to deleted tasks are ignored.
+pub fn open_blockers<'a>(items: &'a [TodoItem], task: &TodoItem) -> Vec<&'a TodoItem> {
+    items
+        .iter()
+        .filter(|item| task.depends_on.contains(&item.id) && !item.status.is_closed())
+        .collect()
+}
+
+/// The tasks that list the given task as one of their prerequisites.
+pub fn dependents(items: &[TodoItem], id: Uuid) -> Vec<&TodoItem> {
+    items
+        .iter()
+        .filter(|item| item.depends_on.contains(&id))
+        .collect()
+}
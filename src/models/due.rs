@@ -0,0 +1,194 @@
+// The due.rs file parses what users type into the due date field and describes how much time is
+// left until a task is due. A due value is either a whole day or a moment in a time zone, e.g.
+// "tomorrow 14:00", "2026-11-02 09:30 Europe/Copenhagen" or "2026-11-02T09:30+01:00".
+
+use chrono::{
+    DateTime, Days, FixedOffset, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone,
+};
+use chrono_tz::Tz;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Due {
+    /// Due some time during the given day.
+    Date(NaiveDate),
+    /// Due at an exact moment, with the time zone it was entered in when that is known by name.
+    /// An offset alone only fixes this one moment; the zone also tells what the same time of day
+    /// is on another date, across daylight saving changes.
+    At(DateTime<FixedOffset>, Option<Tz>),
+}
+
+impl Due {
+    /// Due at `local` time in `zone`. In the hour skipped when clocks go forward there is no
+    /// such time.
+    pub fn at(local: NaiveDateTime, zone: Tz) -> Option<Self> {
+        zone.from_local_datetime(&local)
+            .earliest()
+            .map(|at| Due::At(at.fixed_offset(), Some(zone)))
+    }
+
+    /// Parses `today`, `tomorrow` or `YYYY-MM-DD`, optionally followed by a time (`14:00`,
+    /// `09:30:15`) separated by a space or `T`, and an optional zone (`Z`, `UTC`, `+01:00`,
+    /// `-0500` or a name like `Europe/Copenhagen`). Times without a zone are taken in the local
+    /// time zone.
+    pub fn parse(input: &str, now: DateTime<Local>) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        let (date_part, rest) = if let Some(rest) = input.strip_prefix("tomorrow") {
+            ("tomorrow", rest)
+        } else if let Some(rest) = input.strip_prefix("today") {
+            ("today", rest)
+        } else if input.len() > 10 && input.is_char_boundary(10) {
+            input.split_at(10)
+        } else {
+            (input.as_str(), "")
+        };
+        let rest = rest.trim().trim_start_matches('t').trim();
+
+        let today = now.date_naive();
+        let date = match date_part {
+            "today" => today,
+            "tomorrow" => today.checked_add_days(Days::new(1))?,
+            _ => NaiveDate::parse_from_str(date_part, "%Y-%m-%d").ok()?,
+        };
+        if rest.is_empty() {
+            return Some(Due::Date(date));
+        }
+
+        let (time, zone) = split_zone(rest)?;
+        let time = NaiveTime::parse_from_str(time, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+            .ok()?;
+        let local = NaiveDateTime::new(date, time);
+        match zone.or_else(|| local_zone().map(Zone::Named)) {
+            Some(Zone::Named(zone)) => Due::at(local, zone),
+            Some(Zone::Offset(offset)) => offset
+                .from_local_datetime(&local)
+                .single()
+                .map(|at| Due::At(at, None)),
+            // The local zone has no name here, so only its offset at that moment can be kept
+            None => Local
+                .from_local_datetime(&local)
+                .earliest()
+                .map(|at| Due::At(at.fixed_offset(), None)),
+        }
+    }
+
+    /// The day the task is due, in the time zone it was entered in.
+    pub fn date(self) -> NaiveDate {
+        match self {
+            Due::Date(date) => date,
+            Due::At(at, _) => at.date_naive(),
+        }
+    }
+}
+
+/// Writes the due value in a form `Due::parse` accepts, e.g. "2026-11-02 09:30 Europe/Copenhagen"
+/// or "2026-11-02 09:30 +01:00".
+impl fmt::Display for Due {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Due::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Due::At(at, Some(zone)) => write!(
+                f,
+                "{} {}",
+                at.with_timezone(zone).format("%Y-%m-%d %H:%M"),
+                zone.name()
+            ),
+            Due::At(at, None) => write!(f, "{}", at.format("%Y-%m-%d %H:%M %:z")),
+        }
+    }
+}
+
+/// The local time zone, if the system names it.
+pub fn local_zone() -> Option<Tz> {
+    iana_time_zone::get_timezone().ok()?.parse().ok()
+}
+
+// A zone given after a time
+enum Zone {
+    Offset(FixedOffset),
+    Named(Tz),
+}
+
+// Splits a trailing zone off a time such as "14:00+02:00", "14:00 utc", "14:00z" or
+// "14:00 europe/copenhagen"
+fn split_zone(input: &str) -> Option<(&str, Option<Zone>)> {
+    // Names come first, as some end like a suffix below, e.g. "america/la_paz". "utc" is kept as
+    // an offset.
+    if let Some((time, name)) = input.rsplit_once(' ') {
+        // The input is lowercased, so the name is looked up ignoring case
+        let zone = chrono_tz::TZ_VARIANTS
+            .iter()
+            .find(|zone| name != "utc" && zone.name().eq_ignore_ascii_case(name));
+        if let Some(zone) = zone {
+            return Some((time.trim(), Some(Zone::Named(*zone))));
+        }
+    }
+    for suffix in ["utc", "z"] {
+        let Some(time) = input.strip_suffix(suffix) else {
+            continue;
+        };
+        if time.ends_with(|c: char| c.is_ascii_digit() || c == ' ') {
+            return Some((time.trim(), FixedOffset::east_opt(0).map(Zone::Offset)));
+        }
+    }
+    let Some(sign_at) = input.rfind(['+', '-']) else {
+        return Some((input, None));
+    };
+    let (time, zone) = input.split_at(sign_at);
+    let sign = if zone.starts_with('-') { -1 } else { 1 };
+    let digits: String = zone[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (
+            digits[..2].parse::<i32>().ok()?,
+            digits[2..].parse::<i32>().ok()?,
+        ),
+        _ => return None,
+    };
+    let offset = FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))?;
+    Some((time.trim(), Some(Zone::Offset(offset))))
+}
+
+/// Moves a date forward by an amount such as `3d`, `+2w`, `1m` or `1y`.
+pub fn add_amount(from: NaiveDate, amount: &str) -> Option<NaiveDate> {
+    let amount = amount.trim().trim_start_matches('+').to_lowercase();
+    let unit = amount.chars().last()?;
+    let count: u32 = amount[..amount.len() - unit.len_utf8()].parse().ok()?;
+    match unit {
+        'd' => from.checked_add_days(Days::new(u64::from(count))),
+        'w' => from.checked_add_days(Days::new(7 * u64::from(count))),
+        'm' => from.checked_add_months(Months::new(count)),
+        'y' => from.checked_add_months(Months::new(12 * count)),
+        _ => None,
+    }
+}
+
+/// A short description of how long until something is due, e.g. "in 3h", "today" or
+/// "2d overdue". Date-only deadlines are compared by day.
+pub fn remaining(due: Due, now: DateTime<Local>) -> String {
+    match due {
+        Due::Date(date) => match (date - now.date_naive()).num_days() {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            days if days > 1 => format!("in {}d", days),
+            days => format!("{}d overdue", -days),
+        },
+        Due::At(at, _) => {
+            let minutes = (at.with_timezone(&Local) - now).num_minutes();
+            let span = match minutes.abs() {
+                m if m < 60 => format!("{}m", m),
+                m if m < 48 * 60 => format!("{}h", m / 60),
+                m => format!("{}d", m / (24 * 60)),
+            };
+            if minutes >= 0 {
+                format!("in {}", span)
+            } else {
+                format!("{} overdue", span)
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+// The merge.rs file combines two versions of the task list that were changed independently since
+// a common starting point, e.g. by two TaskMaster windows working on the same file.
+
+use crate::models::TodoItem;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Merges task lists by ID. `base` is the list both sides started from.
+///
+/// A task present on both sides takes the version with the later `updated_at`. A task deleted on
+/// one side stays deleted unless the other side changed it since. The order follows `mine`, with
+/// tasks only found in `theirs` appended in their order.
+pub fn merge(base: &[TodoItem], mine: &[TodoItem], theirs: &[TodoItem]) -> Vec<TodoItem> {
+    let base: HashMap<Uuid, &TodoItem> = base.iter().map(|item| (item.id, item)).collect();
+    let their_items: HashMap<Uuid, &TodoItem> = theirs.iter().map(|item| (item.id, item)).collect();
+    let my_ids: HashSet<Uuid> = mine.iter().map(|item| item.id).collect();
+    // Whether the other side deleted a task that this side left untouched
+    let deleted_elsewhere = |item: &TodoItem| {
+        base.get(&item.id)
+            .is_some_and(|original| original.updated_at == item.updated_at)
+    };
+
+    let mut merged = Vec::with_capacity(mine.len().max(theirs.len()));
+    for item in mine {
+        match their_items.get(&item.id) {
+            Some(their) if their.updated_at > item.updated_at => merged.push((*their).clone()),
+            Some(_) => merged.push(item.clone()),
+            None if deleted_elsewhere(item) => {}
+            None => merged.push(item.clone()),
+        }
+    }
+    for their in theirs {
+        if !my_ids.contains(&their.id) && !deleted_elsewhere(their) {
+            merged.push(their.clone());
+        }
+    }
+    merged
+}
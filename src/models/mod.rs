@@ -1,6 +1,16 @@
+pub mod dependencies;
+pub mod due;
+pub mod merge;
+pub mod recurrence;
+pub mod time_tracking;
 pub mod todo_item;
+pub mod tree;
 
-pub use todo_item::{Status, TodoItem};
+pub use due::Due;
+pub use recurrence::Recurrence;
+pub use time_tracking::TimeEntry;
+pub use todo_item::{Priority, Status, TodoItem};
+pub use tree::TreeRow;
 
 #[cfg(test)]
 mod tests {
@@ -21,6 +31,13 @@ mod tests {
         assert!(todo_item.tags.is_empty());
     }
 
+    #[test]
+    fn test_todo_items_get_unique_ids() {
+        let first = create_todo_item();
+        let second = create_todo_item();
+        assert_ne!(first.id, second.id);
+    }
+
     #[test]
     fn test_set_due_date() {
         let mut todo_item = create_todo_item();
@@ -61,4 +78,524 @@ mod tests {
         assert!(todo_item.tags.contains(&"urgent".to_string()));
         assert!(todo_item.tags.contains(&"work".to_string()));
     }
+
+    #[test]
+    fn test_set_status_tracks_completion_time() {
+        let mut todo_item = create_todo_item();
+        assert!(todo_item.completed_at.is_none());
+
+        todo_item.set_status(Status::Completed);
+        assert!(todo_item.completed_at.is_some());
+        assert_eq!(todo_item.completed_at, Some(todo_item.updated_at));
+
+        todo_item.set_status(Status::Todo);
+        assert!(todo_item.completed_at.is_none());
+        assert!(todo_item.updated_at >= todo_item.created_at);
+    }
+
+    #[test]
+    fn test_priority_cycles_and_orders() {
+        assert_eq!(Priority::default(), Priority::None);
+        assert_eq!(Priority::None.next(), Priority::Low);
+        assert_eq!(Priority::Urgent.next(), Priority::None);
+        assert_eq!(Priority::None.previous(), Priority::Urgent);
+        assert_eq!(Priority::from_digit('3'), Some(Priority::High));
+        assert_eq!(Priority::from_digit('9'), None);
+        assert!(Priority::Urgent > Priority::High);
+        assert!(Priority::Low > Priority::None);
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        assert!(Status::Todo.can_transition_to(Status::Blocked));
+        assert!(Status::Waiting.can_transition_to(Status::Cancelled));
+        assert!(Status::Completed.can_transition_to(Status::Todo));
+        assert!(!Status::Completed.can_transition_to(Status::InProgress));
+        assert!(!Status::Cancelled.can_transition_to(Status::Completed));
+        assert!(!Status::Todo.can_transition_to(Status::Todo));
+
+        let mut todo_item = create_todo_item();
+        todo_item.set_status(Status::Cancelled);
+        assert!(!todo_item.set_status(Status::Completed));
+        assert_eq!(todo_item.status, Status::Cancelled);
+        assert!(todo_item.completed_at.is_none());
+    }
+
+    #[test]
+    fn test_status_cycle() {
+        assert_eq!(Status::Todo.next(), Status::InProgress);
+        assert_eq!(Status::InProgress.next(), Status::Waiting);
+        assert_eq!(Status::Waiting.next(), Status::Blocked);
+        assert_eq!(Status::Blocked.next(), Status::Completed);
+        // Closed tasks can only be reopened
+        assert_eq!(Status::Completed.next(), Status::Todo);
+        assert_eq!(Status::Cancelled.next(), Status::Todo);
+    }
+
+    #[test]
+    fn test_legacy_status_deserializes() {
+        let status: Status = serde_json::from_str("\"Completed\"").unwrap();
+        assert_eq!(status, Status::Completed);
+        let status: Status = serde_json::from_str("\"InProgress\"").unwrap();
+        assert_eq!(status, Status::InProgress);
+    }
+
+    fn create_tree() -> Vec<TodoItem> {
+        // Parent
+        // ├── Child A
+        // │   └── Grandchild
+        // └── Child B
+        // Other
+        let parent = TodoItem::new(Status::Todo, "Parent", "");
+        let mut child_a = TodoItem::new(Status::Completed, "Child A", "");
+        child_a.parent_id = Some(parent.id);
+        let mut grandchild = TodoItem::new(Status::Todo, "Grandchild", "");
+        grandchild.parent_id = Some(child_a.id);
+        let other = TodoItem::new(Status::Todo, "Other", "");
+        let mut child_b = TodoItem::new(Status::Todo, "Child B", "");
+        child_b.parent_id = Some(parent.id);
+        vec![parent, child_a, grandchild, other, child_b]
+    }
+
+    #[test]
+    fn test_visible_rows_follow_tree_order() {
+        let items = create_tree();
+        let rows = tree::visible_rows(&items, |_| true);
+        let order: Vec<(&str, usize)> = rows
+            .iter()
+            .map(|row| (items[row.index].todo.as_str(), row.depth))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("Parent", 0),
+                ("Child A", 1),
+                ("Grandchild", 2),
+                ("Child B", 1),
+                ("Other", 0)
+            ]
+        );
+        assert!(rows[0].has_children);
+        assert!(!rows[2].has_children);
+    }
+
+    #[test]
+    fn test_visible_rows_skip_collapsed_children() {
+        let mut items = create_tree();
+        items[1].collapsed = true;
+        let rows = tree::visible_rows(&items, |_| true);
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|row| items[row.index].todo != "Grandchild"));
+    }
+
+    #[test]
+    fn test_visible_rows_survive_parent_cycles() {
+        let mut items = create_tree();
+        let (first, second) = (items[0].id, items[3].id);
+        items[0].parent_id = Some(second);
+        items[3].parent_id = Some(first);
+        assert_eq!(tree::visible_rows(&items, |_| true).len(), items.len());
+    }
+
+    #[test]
+    fn test_descendants_and_progress() {
+        let items = create_tree();
+        assert_eq!(tree::descendants(&items, items[0].id).len(), 3);
+        assert!(tree::descendants(&items, items[3].id).is_empty());
+        assert_eq!(tree::child_progress(&items, items[0].id), (1, 2));
+        assert_eq!(tree::child_progress(&items, items[3].id), (0, 0));
+    }
+
+    #[test]
+    fn test_dependency_cycles_are_detected() {
+        let mut first = create_todo_item();
+        let mut second = create_todo_item();
+        let third = create_todo_item();
+        second.depends_on.push(third.id);
+        first.depends_on.push(second.id);
+        let items = vec![first.clone(), second.clone(), third.clone()];
+
+        assert!(dependencies::would_create_cycle(&items, third.id, first.id));
+        assert!(dependencies::would_create_cycle(&items, first.id, first.id));
+        assert!(!dependencies::would_create_cycle(
+            &items, first.id, third.id
+        ));
+    }
+
+    #[test]
+    fn test_open_blockers_and_dependents() {
+        let mut task = create_todo_item();
+        let open = create_todo_item();
+        let done = TodoItem::new(Status::Completed, "Done", "");
+        task.depends_on = vec![open.id, done.id, uuid::Uuid::new_v4()];
+        let items = vec![task.clone(), open.clone(), done.clone()];
+
+        let blockers = dependencies::open_blockers(&items, &task);
+        assert_eq!(blockers.len(), 1);
+        assert_eq!(blockers[0].id, open.id);
+        assert_eq!(dependencies::dependents(&items, done.id).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_recurrence_rules() {
+        use chrono::Weekday;
+        use recurrence::{Frequency, RecurrenceMode};
+
+        let rule = Recurrence::parse("every 2 weeks on Mon, thu after completion").unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.weekdays, vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(rule.mode, RecurrenceMode::AfterCompletion);
+
+        let rule = Recurrence::parse("every fri,mon").unwrap();
+        assert_eq!(rule.weekdays, vec![Weekday::Mon, Weekday::Fri]);
+        assert_eq!(
+            Recurrence::parse("monthly").unwrap().frequency,
+            Frequency::Monthly
+        );
+        assert_eq!(Recurrence::parse("every 3 days").unwrap().interval, 3);
+
+        assert!(Recurrence::parse("every 0 days").is_none());
+        assert!(Recurrence::parse("monthly on mon").is_none());
+        assert!(Recurrence::parse("sometimes").is_none());
+
+        // Descriptions parse back into the same rule
+        let rule = Recurrence::parse("every 2 weeks on mon,thu after completion").unwrap();
+        assert_eq!(Recurrence::parse(&rule.describe()), Some(rule));
+    }
+
+    #[test]
+    fn test_next_due_date() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        // 2024-08-14 is a Wednesday
+        let due = Some(date("2024-08-14"));
+        let completed = date("2024-08-20");
+
+        let weekly = Recurrence::parse("weekly").unwrap();
+        assert_eq!(
+            weekly.next_due_date(due, completed),
+            Some(date("2024-08-21"))
+        );
+
+        let after = Recurrence::parse("weekly after completion").unwrap();
+        assert_eq!(
+            after.next_due_date(due, completed),
+            Some(date("2024-08-27"))
+        );
+
+        let days = Recurrence::parse("every mon,thu").unwrap();
+        assert_eq!(days.next_due_date(due, completed), Some(date("2024-08-15")));
+
+        let fortnightly = Recurrence::parse("every 2 weeks on mon").unwrap();
+        assert_eq!(
+            fortnightly.next_due_date(due, completed),
+            Some(date("2024-08-26"))
+        );
+
+        // Month ends are clamped
+        let monthly = Recurrence::parse("monthly").unwrap();
+        assert_eq!(
+            monthly.next_due_date(Some(date("2024-01-31")), completed),
+            Some(date("2024-02-29"))
+        );
+    }
+
+    #[test]
+    fn test_next_occurrence_takes_over_recurrence() {
+        let mut todo_item = create_todo_item();
+        todo_item.set_due_date("2024-08-14").unwrap();
+        todo_item.recurrence = Recurrence::parse("daily");
+
+        let next = todo_item
+            .next_occurrence(NaiveDate::from_ymd_opt(2024, 8, 14).unwrap())
+            .unwrap();
+        assert_ne!(next.id, todo_item.id);
+        assert_eq!(next.status, Status::Todo);
+        assert_eq!(next.due_date, NaiveDate::from_ymd_opt(2024, 8, 15));
+        assert!(next.recurrence.is_some());
+        assert!(todo_item.recurrence.is_none());
+    }
+
+    #[test]
+    fn test_next_occurrence_keeps_local_time_across_dst() {
+        let mut todo_item = create_todo_item();
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        todo_item.set_due(Due::at(
+            monday.and_hms_opt(9, 0, 0).unwrap(),
+            chrono_tz::Europe::Copenhagen,
+        ));
+        todo_item.recurrence = Recurrence::parse("weekly");
+
+        // Summer time ends in between, so the offset changes but 09:00 stays 09:00
+        let next = todo_item.next_occurrence(monday).unwrap();
+        let Some(Due::At(at, zone)) = next.due() else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-10-26T09:00:00+01:00");
+        assert_eq!(zone, Some(chrono_tz::Europe::Copenhagen));
+    }
+
+    #[test]
+    fn test_parse_due_values() {
+        use chrono::{Local, TimeZone};
+        let now = Local.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+        assert_eq!(
+            Due::parse("2026-11-02", now),
+            Some(Due::Date(date("2026-11-02")))
+        );
+        assert_eq!(
+            Due::parse("Today", now),
+            Some(Due::Date(date("2026-10-18")))
+        );
+
+        let Some(Due::At(at, None)) = Due::parse("2026-11-02T09:30+01:00", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-11-02T09:30:00+01:00");
+
+        let Some(Due::At(at, None)) = Due::parse("tomorrow 14:00 UTC", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-10-19T14:00:00+00:00");
+
+        // Named zones are matched ignoring case and kept
+        let Some(Due::At(at, zone)) = Due::parse("2026-11-02 09:30 Europe/Copenhagen", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-11-02T09:30:00+01:00");
+        assert_eq!(zone, Some(chrono_tz::Europe::Copenhagen));
+
+        // A name ending in "z" is not mistaken for UTC
+        let Some(Due::At(at, zone)) = Due::parse("2026-11-02 09:30 America/La_Paz", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-11-02T09:30:00-04:00");
+        assert_eq!(zone, Some(chrono_tz::America::La_Paz));
+        let Some(Due::At(at, None)) = Due::parse("2026-11-02 09:30z", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.to_rfc3339(), "2026-11-02T09:30:00+00:00");
+
+        // Without a zone the local time zone is used
+        let Some(Due::At(at, zone)) = Due::parse("2026-11-02 09:30", now) else {
+            panic!("expected a date and time");
+        };
+        assert_eq!(at.date_naive(), date("2026-11-02"));
+        assert_eq!(zone, due::local_zone());
+
+        assert!(Due::parse("2026-11-02 25:00", now).is_none());
+        assert!(Due::parse("2026-11-02 09:30+1", now).is_none());
+        assert!(Due::parse("someday", now).is_none());
+    }
+
+    #[test]
+    fn test_due_display_parses_back() {
+        let now = chrono::Local::now();
+        let due = Due::parse("2026-11-02T09:30-05:00", now).unwrap();
+        assert_eq!(due.to_string(), "2026-11-02 09:30 -05:00");
+        assert_eq!(Due::parse(&due.to_string(), now), Some(due));
+
+        let due = Due::parse("2026-11-02T09:30 America/New_York", now).unwrap();
+        assert_eq!(due.to_string(), "2026-11-02 09:30 America/New_York");
+        assert_eq!(Due::parse(&due.to_string(), now), Some(due));
+    }
+
+    #[test]
+    fn test_remaining_time() {
+        use chrono::{Duration, Local, TimeZone};
+        let now = Local.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap();
+        let at = |d: Duration| Due::At((now + d).fixed_offset(), None);
+
+        assert_eq!(due::remaining(at(Duration::hours(3)), now), "in 3h");
+        assert_eq!(due::remaining(at(Duration::minutes(20)), now), "in 20m");
+        assert_eq!(due::remaining(at(Duration::days(3)), now), "in 3d");
+        assert_eq!(due::remaining(at(-Duration::hours(5)), now), "5h overdue");
+
+        let today = now.date_naive();
+        assert_eq!(due::remaining(Due::Date(today), now), "today");
+        assert_eq!(
+            due::remaining(Due::Date(today - Duration::days(2)), now),
+            "2d overdue"
+        );
+    }
+
+    #[test]
+    fn test_set_due_keeps_date_in_step() {
+        let mut todo_item = create_todo_item();
+        let due = Due::parse("2026-11-02 09:30+01:00", chrono::Local::now());
+        todo_item.set_due(due);
+        assert_eq!(todo_item.due_date, NaiveDate::from_ymd_opt(2026, 11, 2));
+        assert_eq!(todo_item.due(), due);
+
+        todo_item.set_due_date("2026-12-24").unwrap();
+        assert!(todo_item.due_at.is_none());
+    }
+
+    #[test]
+    fn test_visible_rows_skip_filtered_subtrees() {
+        let items = create_tree();
+        let rows = tree::visible_rows(&items, |item| item.todo != "Child A");
+        let names: Vec<&str> = rows
+            .iter()
+            .map(|row| items[row.index].todo.as_str())
+            .collect();
+        assert_eq!(names, vec!["Parent", "Child B", "Other"]);
+    }
+
+    #[test]
+    fn test_deferred_tasks() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap();
+        let mut todo_item = create_todo_item();
+        assert!(!todo_item.is_deferred(today));
+        todo_item.scheduled = today.succ_opt();
+        assert!(todo_item.is_deferred(today));
+        todo_item.scheduled = Some(today);
+        assert!(!todo_item.is_deferred(today));
+    }
+
+    #[test]
+    fn test_add_amount() {
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        let from = date("2026-01-31");
+        assert_eq!(due::add_amount(from, "3d"), Some(date("2026-02-03")));
+        assert_eq!(due::add_amount(from, "+2w"), Some(date("2026-02-14")));
+        assert_eq!(due::add_amount(from, "1m"), Some(date("2026-02-28")));
+        assert_eq!(due::add_amount(from, "1y"), Some(date("2027-01-31")));
+        assert_eq!(due::add_amount(from, "3"), None);
+        assert_eq!(due::add_amount(from, "xd"), None);
+    }
+
+    #[test]
+    fn test_timer_start_and_stop() {
+        use chrono::{Duration, Utc};
+        let start = Utc::now() - Duration::minutes(90);
+        let mut todo_item = create_todo_item();
+
+        todo_item.start_timer(start);
+        todo_item.start_timer(start + Duration::minutes(5)); // Already running, ignored
+        assert!(todo_item.is_timer_running());
+        assert_eq!(todo_item.time_log.len(), 1);
+
+        todo_item.stop_timer(start + Duration::minutes(30));
+        assert!(!todo_item.is_timer_running());
+        assert_eq!(
+            time_tracking::total(&todo_item.time_log, Utc::now()),
+            Duration::minutes(30)
+        );
+        assert_eq!(
+            time_tracking::format_clock(Duration::seconds(3909)),
+            "1:05:09"
+        );
+    }
+
+    #[test]
+    fn test_running_timer_survives_round_trip() {
+        let mut todo_item = create_todo_item();
+        let start = chrono::Utc::now() - chrono::Duration::hours(2);
+        todo_item.start_timer(start);
+
+        let json = serde_json::to_string(&todo_item).unwrap();
+        let loaded: TodoItem = serde_json::from_str(&json).unwrap();
+
+        assert!(loaded.is_timer_running());
+        let elapsed = time_tracking::total(&loaded.time_log, chrono::Utc::now());
+        assert!(elapsed >= chrono::Duration::hours(2));
+    }
+
+    #[test]
+    fn test_parse_time_entries() {
+        let now = chrono::Local::now();
+        let entry = TimeEntry::parse("2026-10-18 09:00 - 10:30", now).unwrap();
+        assert_eq!(entry.duration(now.to_utc()), chrono::Duration::minutes(90));
+        assert_eq!(entry.describe(), "2026-10-18 09:00 - 10:30");
+
+        let overnight = TimeEntry::parse("2026-10-18 23:00 - 2026-10-19 01:00", now).unwrap();
+        assert_eq!(overnight.duration(now.to_utc()), chrono::Duration::hours(2));
+
+        let running = TimeEntry::parse("2026-10-18 09:00 - running", now).unwrap();
+        assert!(running.is_running());
+        assert_eq!(TimeEntry::parse(&running.describe(), now), Some(running));
+
+        assert!(TimeEntry::parse("2026-10-18 10:30 - 09:00", now).is_none());
+        assert!(TimeEntry::parse("2026-10-18 - 2026-10-19", now).is_none());
+    }
+
+    #[test]
+    fn test_parse_estimates() {
+        assert_eq!(time_tracking::parse_estimate("30m"), Some(30));
+        assert_eq!(time_tracking::parse_estimate("2h"), Some(120));
+        assert_eq!(time_tracking::parse_estimate("1.5h"), Some(90));
+        assert_eq!(time_tracking::parse_estimate("1h 30m"), Some(90));
+        assert_eq!(time_tracking::parse_estimate("1d"), Some(8 * 60));
+        assert_eq!(time_tracking::parse_estimate("2"), None);
+        assert_eq!(time_tracking::parse_estimate("2 weeks"), None);
+        assert_eq!(time_tracking::parse_estimate("250d"), Some(250 * 8 * 60));
+        assert_eq!(time_tracking::parse_estimate("99999999h"), None);
+
+        assert_eq!(time_tracking::format_minutes(90), "1h 30m");
+        assert_eq!(time_tracking::format_minutes(45), "45m");
+        assert_eq!(time_tracking::format_minutes(480), "8h");
+    }
+
+    #[test]
+    fn test_compare_to_estimate() {
+        assert_eq!(
+            time_tracking::compare_to_estimate(120, chrono::Duration::minutes(45)),
+            "tracked 0:45:00, 1h 15m left"
+        );
+        assert_eq!(
+            time_tracking::compare_to_estimate(120, chrono::Duration::minutes(150)),
+            "tracked 2:30:00, 30m over, +25%"
+        );
+        assert_eq!(
+            time_tracking::compare_to_estimate(120, chrono::Duration::zero()),
+            "nothing tracked yet"
+        );
+    }
+
+    #[test]
+    fn test_merge_combines_independent_changes() {
+        let base = vec![
+            TodoItem::new(Status::Todo, "Edited here", ""),
+            TodoItem::new(Status::Todo, "Edited there", ""),
+            TodoItem::new(Status::Todo, "Deleted here", ""),
+            TodoItem::new(Status::Todo, "Deleted there", ""),
+        ];
+        let later = chrono::Utc::now() + chrono::Duration::seconds(5);
+
+        let mut mine = base.clone();
+        mine[0].info = "mine".to_string();
+        mine[0].updated_at = later;
+        mine.remove(2);
+        mine.push(TodoItem::new(Status::Todo, "Added here", ""));
+
+        let mut theirs = base.clone();
+        theirs[1].info = "theirs".to_string();
+        theirs[1].updated_at = later;
+        theirs.remove(3);
+        theirs.push(TodoItem::new(Status::Todo, "Added there", ""));
+
+        let merged = merge::merge(&base, &mine, &theirs);
+        let names: Vec<_> = merged.iter().map(|item| item.todo.as_str()).collect();
+        assert_eq!(
+            names,
+            ["Edited here", "Edited there", "Added here", "Added there"]
+        );
+        assert_eq!(merged[0].info, "mine");
+        assert_eq!(merged[1].info, "theirs");
+    }
+
+    #[test]
+    fn test_merge_keeps_task_changed_after_deletion_elsewhere() {
+        let base = vec![TodoItem::new(Status::Todo, "Task", "")];
+        let mut mine = base.clone();
+        mine[0].set_status(Status::InProgress);
+        mine[0].updated_at = chrono::Utc::now() + chrono::Duration::seconds(5);
+
+        let merged = merge::merge(&base, &mine, &[]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].status, Status::InProgress);
+    }
 }
@@ -0,0 +1,181 @@
+// The recurrence.rs file describes how a task repeats and works out the due date of the next
+// occurrence. Rules are written and read in a small text format, e.g. "every 2 weeks on mon,thu"
+// or "monthly after completion", so they can be typed straight into the edit form.
+
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecurrenceMode {
+    /// The next occurrence follows the previous due date, however late the task was finished.
+    #[default]
+    FixedSchedule,
+    /// The next occurrence is counted from the day the task was completed.
+    AfterCompletion,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    /// Repeat every `interval` days, weeks, months or years.
+    pub interval: u32,
+    /// Days of the week a weekly rule falls on. Empty means the weekday of the previous due date.
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+    #[serde(default)]
+    pub mode: RecurrenceMode,
+}
+
+impl Recurrence {
+    /// Parses rules such as `daily`, `weekly on mon,fri`, `every 3 months`, `every tue,thu` or
+    /// `every 2 weeks after completion`.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        let (rule, mode) = match input.strip_suffix("after completion") {
+            Some(rule) => (rule.trim_end(), RecurrenceMode::AfterCompletion),
+            None => (input.as_str(), RecurrenceMode::FixedSchedule),
+        };
+        let (rule, weekdays) = match rule.split_once(" on ") {
+            Some((rule, days)) => (rule.trim(), Some(parse_weekdays(days)?)),
+            None => (rule, None),
+        };
+
+        let mut words = rule.split_whitespace();
+        let (frequency, interval, listed_days) = match words.next()? {
+            "daily" => (Frequency::Daily, 1, None),
+            "weekly" => (Frequency::Weekly, 1, None),
+            "monthly" => (Frequency::Monthly, 1, None),
+            "yearly" | "annually" => (Frequency::Yearly, 1, None),
+            "every" => {
+                let next = words.next()?;
+                match next.parse::<u32>() {
+                    Ok(interval) => (parse_unit(words.next()?)?, interval, None),
+                    Err(_) => match parse_unit(next) {
+                        Some(frequency) => (frequency, 1, None),
+                        None => {
+                            // Allow spaces in the list, e.g. "every mon, thu"
+                            let days: String =
+                                std::iter::once(next).chain(words.by_ref()).collect();
+                            (Frequency::Weekly, 1, Some(parse_weekdays(&days)?))
+                        }
+                    },
+                }
+            }
+            _ => return None,
+        };
+        if words.next().is_some() || interval == 0 {
+            return None;
+        }
+
+        let weekdays = match (weekdays, listed_days) {
+            (Some(_), Some(_)) => return None,
+            (Some(days), None) | (None, Some(days)) => days,
+            (None, None) => Vec::new(),
+        };
+        if !weekdays.is_empty() && frequency != Frequency::Weekly {
+            return None;
+        }
+
+        Some(Self {
+            frequency,
+            interval,
+            weekdays,
+            mode,
+        })
+    }
+
+    /// Writes the rule back in the format accepted by `parse`.
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        };
+        let mut text = match self.interval {
+            1 => format!("every {}", unit),
+            n => format!("every {} {}s", n, unit),
+        };
+        if !self.weekdays.is_empty() {
+            let days: Vec<String> = self
+                .weekdays
+                .iter()
+                .map(|day| day.to_string().to_lowercase())
+                .collect();
+            text.push_str(&format!(" on {}", days.join(",")));
+        }
+        if self.mode == RecurrenceMode::AfterCompletion {
+            text.push_str(" after completion");
+        }
+        text
+    }
+
+    /// The due date of the occurrence after one that was due on `due` (if it had a due date) and
+    /// completed on `completed`.
+    pub fn next_due_date(&self, due: Option<NaiveDate>, completed: NaiveDate) -> Option<NaiveDate> {
+        let base = match self.mode {
+            RecurrenceMode::FixedSchedule => due.unwrap_or(completed),
+            RecurrenceMode::AfterCompletion => completed,
+        };
+        match self.frequency {
+            Frequency::Daily => base.checked_add_days(Days::new(u64::from(self.interval))),
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                base.checked_add_days(Days::new(7 * u64::from(self.interval)))
+            }
+            Frequency::Weekly => self.next_listed_weekday(base),
+            Frequency::Monthly => base.checked_add_months(Months::new(self.interval)),
+            Frequency::Yearly => base.checked_add_months(Months::new(12 * self.interval)),
+        }
+    }
+
+    // The first listed weekday after `base`, either later in the same week or in the first week
+    // of the next cycle `interval` weeks on.
+    fn next_listed_weekday(&self, base: NaiveDate) -> Option<NaiveDate> {
+        let is_listed = |date: &NaiveDate| self.weekdays.contains(&date.weekday());
+        let week_start =
+            base.checked_sub_days(Days::new(u64::from(base.weekday().num_days_from_monday())))?;
+        let later_this_week = base
+            .iter_days()
+            .skip(1)
+            .take_while(|date| date.weekday() != Weekday::Mon)
+            .find(is_listed);
+        later_this_week.or_else(|| {
+            week_start
+                .checked_add_days(Days::new(7 * u64::from(self.interval)))?
+                .iter_days()
+                .take(7)
+                .find(is_listed)
+        })
+    }
+}
+
+fn parse_unit(word: &str) -> Option<Frequency> {
+    match word.trim_end_matches('s') {
+        "day" => Some(Frequency::Daily),
+        "week" => Some(Frequency::Weekly),
+        "month" => Some(Frequency::Monthly),
+        "year" => Some(Frequency::Yearly),
+        _ => None,
+    }
+}
+
+// Comma separated weekday names, e.g. "mon,wed,fri" or "monday, thursday"
+fn parse_weekdays(input: &str) -> Option<Vec<Weekday>> {
+    let mut days = Vec::new();
+    for day in input.split(',') {
+        let day: Weekday = day.trim().parse().ok()?;
+        if !days.contains(&day) {
+            days.push(day);
+        }
+    }
+    days.sort_by_key(|day| day.num_days_from_monday());
+    Some(days)
+}
@@ -0,0 +1,160 @@
+// The time_tracking.rs file holds the intervals logged against a task. A running timer is simply
+// an interval without an end, so it keeps counting across restarts.
+
+use crate::models::Due;
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub start: DateTime<Utc>,
+    /// `None` while the timer is running.
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeEntry {
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+
+    /// Time covered by the entry, counting a running timer up to `now`.
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        (self.end.unwrap_or(now) - self.start).max(Duration::zero())
+    }
+
+    /// Parses entries written by `describe`, e.g. `2026-10-18 09:00 - 10:30`,
+    /// `2026-10-18 23:00 - 2026-10-19 01:00` or `2026-10-18 09:00 - running`.
+    pub fn parse(input: &str, now: DateTime<Local>) -> Option<Self> {
+        let (start, end) = input.split_once(" - ")?;
+        let Due::At(start, _) = Due::parse(start, now)? else {
+            return None;
+        };
+        let end = match end.trim() {
+            "running" => None,
+            end => {
+                // A bare time ends on the day the entry started
+                let end = if end.len() <= 5 {
+                    format!("{} {}", start.date_naive(), end)
+                } else {
+                    end.to_string()
+                };
+                match Due::parse(&end, now)? {
+                    Due::At(end, _) => Some(end),
+                    Due::Date(_) => return None,
+                }
+            }
+        };
+        let entry = Self {
+            start: start.to_utc(),
+            end: end.map(|end| end.to_utc()),
+        };
+        (entry.end.is_none_or(|end| end >= entry.start)).then_some(entry)
+    }
+
+    /// Writes the entry in local time in the format accepted by `parse`.
+    pub fn describe(&self) -> String {
+        let start = self.start.with_timezone(&Local);
+        let end = match self.end.map(|end| end.with_timezone(&Local)) {
+            None => "running".to_string(),
+            Some(end) if end.date_naive() == start.date_naive() => end.format("%H:%M").to_string(),
+            Some(end) => end.format("%Y-%m-%d %H:%M").to_string(),
+        };
+        format!("{} - {}", start.format("%Y-%m-%d %H:%M"), end)
+    }
+}
+
+/// Sum of all logged intervals, counting a running timer up to `now`.
+pub fn total(entries: &[TimeEntry], now: DateTime<Utc>) -> Duration {
+    entries
+        .iter()
+        .map(|entry| entry.duration(now))
+        .fold(Duration::zero(), |sum, d| sum + d)
+}
+
+/// Formats a duration as a clock, e.g. `1:05:09`.
+pub fn format_clock(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Minutes in a working day, used for estimates given in days.
+pub const MINUTES_PER_DAY: u32 = 8 * 60;
+
+/// The largest estimate taken, a year of working days. Anything bigger is a typo rather than a
+/// plan.
+pub const MAX_ESTIMATE_DAYS: u32 = 250;
+
+/// Parses an estimate such as `30m`, `2h`, `1.5h`, `1d` or `1h30m` into minutes. A day counts
+/// as a working day of eight hours. Estimates over `MAX_ESTIMATE_DAYS` are rejected.
+pub fn parse_estimate(input: &str) -> Option<u32> {
+    let input = input.trim().to_lowercase().replace(' ', "");
+    if input.is_empty() {
+        return None;
+    }
+    let mut minutes = 0.0;
+    let mut number = String::new();
+    for c in input.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            'm' | 'h' | 'd' => {
+                let value: f64 = number.parse().ok()?;
+                minutes += value
+                    * match c {
+                        'm' => 1.0,
+                        'h' => 60.0,
+                        _ => f64::from(MINUTES_PER_DAY),
+                    };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    // Every number needs a unit
+    if !number.is_empty() || minutes > f64::from(MAX_ESTIMATE_DAYS * MINUTES_PER_DAY) {
+        return None;
+    }
+    Some(minutes.round() as u32)
+}
+
+/// Formats minutes as hours and minutes, e.g. `2h 30m`, `45m` or `12h`.
+pub fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+/// Compares tracked time with an estimate in minutes, e.g. "tracked 0:45:00, 1h 15m left" or
+/// "tracked 2:30:00, 30m over, +25%".
+pub fn compare_to_estimate(estimate_minutes: u32, tracked: Duration) -> String {
+    let tracked_minutes = tracked.num_minutes().max(0);
+    let difference = i64::from(estimate_minutes) - tracked_minutes;
+    if tracked_minutes == 0 {
+        "nothing tracked yet".to_string()
+    } else if difference >= 0 {
+        format!(
+            "tracked {}, {} left",
+            format_clock(tracked),
+            format_minutes(difference as u32)
+        )
+    } else if estimate_minutes == 0 {
+        format!(
+            "tracked {}, {} over",
+            format_clock(tracked),
+            format_minutes(-difference as u32)
+        )
+    } else {
+        format!(
+            "tracked {}, {} over, +{}%",
+            format_clock(tracked),
+            format_minutes(-difference as u32),
+            -difference * 100 / i64::from(estimate_minutes)
+        )
+    }
+}
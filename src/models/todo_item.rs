@@ -1,39 +1,295 @@
-use chrono::{NaiveDate, ParseError};
+use crate::models::{due, Due, Recurrence, TimeEntry};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Offset, ParseError, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TodoItem {
+    // Files written before tasks had IDs get a fresh one on load, which sticks after the next save
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
     pub todo: String,
     pub info: String,
     pub status: Status,
     pub due_date: Option<NaiveDate>,
+    // Exact deadline for tasks due at a time of day; `due_date` then holds its date
+    #[serde(default)]
+    pub due_at: Option<DateTime<FixedOffset>>,
+    // Time zone `due_at` was entered in, if known by name, so recurring tasks keep their time of
+    // day across daylight saving changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_zone: Option<Tz>,
+    // Edit form buffer for the due date; older versions also wrote it to disk
+    #[serde(skip)]
     pub due_date_temp: Option<String>,
+    // Day the task becomes relevant; until then it is hidden from the default list view
+    #[serde(default)]
+    pub scheduled: Option<NaiveDate>,
     pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
+    // The task this one is a subtask of; `None` for top-level tasks
+    #[serde(default)]
+    pub parent_id: Option<Uuid>,
+    // Whether the subtasks are hidden in the tree view
+    #[serde(default)]
+    pub collapsed: bool,
+    // IDs of the tasks that have to be done before this one can start
+    #[serde(default)]
+    pub depends_on: Vec<Uuid>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    // Edit form buffer for the recurrence rule, like `due_date_temp`
+    #[serde(skip)]
+    pub recurrence_temp: Option<String>,
+    #[serde(default)]
+    pub time_log: Vec<TimeEntry>,
+    // How long the task is expected to take, in minutes
+    #[serde(default)]
+    pub estimate_minutes: Option<u32>,
+    // Edit form buffer for the estimate, like `due_date_temp`
+    #[serde(skip)]
+    pub estimate_temp: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Status {
     Todo,
     Completed,
+    InProgress,
+    Blocked,
+    Waiting,
+    Cancelled,
+}
+
+impl Status {
+    /// Order in which the status cycles when stepping through it from the task list.
+    const CYCLE: [Status; 6] = [
+        Status::Todo,
+        Status::InProgress,
+        Status::Waiting,
+        Status::Blocked,
+        Status::Completed,
+        Status::Cancelled,
+    ];
+
+    /// Completed and cancelled tasks are closed; everything else still needs attention.
+    pub fn is_closed(self) -> bool {
+        matches!(self, Status::Completed | Status::Cancelled)
+    }
+
+    /// Open tasks may move to any other status, closed tasks can only be reopened as `Todo`.
+    pub fn can_transition_to(self, to: Status) -> bool {
+        self != to && (!self.is_closed() || to == Status::Todo)
+    }
+
+    /// The next status in the cycle that can be reached from this one.
+    pub fn next(self) -> Self {
+        let start = Self::CYCLE.iter().position(|s| *s == self).unwrap_or(0);
+        (1..Self::CYCLE.len())
+            .map(|offset| Self::CYCLE[(start + offset) % Self::CYCLE.len()])
+            .find(|candidate| self.can_transition_to(*candidate))
+            .unwrap_or(self)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Status::Todo => "TODO",
+            Status::InProgress => "IN PROGRESS",
+            Status::Blocked => "BLOCKED",
+            Status::Waiting => "WAITING",
+            Status::Completed => "DONE",
+            Status::Cancelled => "CANCELLED",
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 5] = [
+        Priority::None,
+        Priority::Low,
+        Priority::Medium,
+        Priority::High,
+        Priority::Urgent,
+    ];
+
+    /// The next higher priority, wrapping from `Urgent` back to `None`.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// The next lower priority, wrapping from `None` up to `Urgent`.
+    pub fn previous(self) -> Self {
+        Self::ALL[(self as usize + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Maps the digits `0`-`4` onto the priority levels.
+    pub fn from_digit(c: char) -> Option<Self> {
+        c.to_digit(10)
+            .and_then(|d| Self::ALL.get(d as usize))
+            .copied()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::None => "None",
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+            Priority::Urgent => "Urgent",
+        }
+    }
 }
 
 impl TodoItem {
     pub fn new(status: Status, todo: &str, info: &str) -> Self {
+        let now = Utc::now();
         Self {
+            id: Uuid::new_v4(),
             status,
             todo: todo.to_string(),
             info: info.to_string(),
             due_date: None,
+            due_at: None,
+            due_zone: None,
             due_date_temp: None,
+            scheduled: None,
             tags: Vec::new(),
+            priority: Priority::None,
+            parent_id: None,
+            collapsed: false,
+            depends_on: Vec::new(),
+            recurrence: None,
+            recurrence_temp: None,
+            time_log: Vec::new(),
+            estimate_minutes: None,
+            estimate_temp: None,
+            created_at: now,
+            updated_at: now,
+            completed_at: (status == Status::Completed).then_some(now),
+        }
+    }
+
+    /// Marks the task as modified now.
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+
+    /// Changes the status if the transition is allowed and keeps `completed_at` in step with it.
+    /// Returns whether the status was changed.
+    pub fn set_status(&mut self, status: Status) -> bool {
+        if !self.status.can_transition_to(status) {
+            return false;
         }
+        self.status = status;
+        self.touch();
+        self.completed_at = (status == Status::Completed).then_some(self.updated_at);
+        true
     }
 
     pub fn set_due_date(&mut self, date_str: &str) -> Result<(), ParseError> {
-        self.due_date = Some(NaiveDate::parse_from_str(date_str, "%Y-%m-%d")?);
+        self.set_due(Some(Due::Date(NaiveDate::parse_from_str(
+            date_str, "%Y-%m-%d",
+        )?)));
         Ok(())
     }
 
+    pub fn due(&self) -> Option<Due> {
+        self.due_at
+            .map(|at| Due::At(at, self.due_zone))
+            .or(self.due_date.map(Due::Date))
+    }
+
+    pub fn set_due(&mut self, due: Option<Due>) {
+        self.due_date = due.map(Due::date);
+        (self.due_at, self.due_zone) = match due {
+            Some(Due::At(at, zone)) => (Some(at), zone),
+            _ => (None, None),
+        };
+    }
+
+    /// Builds the next occurrence of a recurring task that was completed on `completed`. The
+    /// recurrence rule moves to the new task so that reopening this one cannot repeat it twice.
+    pub fn next_occurrence(&mut self, completed: NaiveDate) -> Option<TodoItem> {
+        let recurrence = self.recurrence.take()?;
+        let due_date = recurrence.next_due_date(self.due_date, completed);
+        let mut next = TodoItem::new(Status::Todo, &self.todo, &self.info);
+        // Timed tasks keep their time of day in their time zone. Tasks saved with just an offset
+        // are taken to be in the local zone if that matches; otherwise they keep the offset.
+        let zone = self.due_zone.or_else(|| {
+            let at = self.due_at?;
+            due::local_zone()
+                .filter(|zone| zone.offset_from_utc_datetime(&at.naive_utc()).fix() == *at.offset())
+        });
+        next.set_due(match (due_date, self.due_at, zone) {
+            (Some(date), Some(at), Some(zone)) => Due::at(
+                NaiveDateTime::new(date, at.with_timezone(&zone).time()),
+                zone,
+            ),
+            (Some(date), Some(at), None) => NaiveDateTime::new(date, at.time())
+                .and_local_timezone(*at.offset())
+                .single()
+                .map(|at| Due::At(at, None)),
+            (date, _, _) => date.map(Due::Date),
+        });
+        // Deferred tasks keep the same lead time before their due date
+        next.scheduled = match (self.scheduled, self.due_date, due_date) {
+            (Some(scheduled), Some(old_due), Some(new_due)) => {
+                Some(scheduled + (new_due - old_due))
+            }
+            _ => None,
+        };
+        next.tags = self.tags.clone();
+        next.priority = self.priority;
+        next.estimate_minutes = self.estimate_minutes;
+        next.parent_id = self.parent_id;
+        next.recurrence = Some(recurrence);
+        Some(next)
+    }
+
+    /// Whether the task has been deferred to a day after `today`.
+    pub fn is_deferred(&self, today: NaiveDate) -> bool {
+        self.scheduled.is_some_and(|scheduled| scheduled > today)
+    }
+
+    pub fn is_timer_running(&self) -> bool {
+        self.time_log.iter().any(TimeEntry::is_running)
+    }
+
+    pub fn start_timer(&mut self, now: DateTime<Utc>) {
+        if !self.is_timer_running() {
+            self.time_log.push(TimeEntry {
+                start: now,
+                end: None,
+            });
+        }
+    }
+
+    pub fn stop_timer(&mut self, now: DateTime<Utc>) {
+        for entry in self.time_log.iter_mut().filter(|entry| entry.is_running()) {
+            entry.end = Some(now.max(entry.start));
+        }
+    }
+
     pub fn add_tag(&mut self, tag: &str) {
         if !self.tags.contains(&tag.to_string()) {
             self.tags.push(tag.to_string());
@@ -0,0 +1,92 @@
+// The tree.rs file derives the task hierarchy from the flat list of items. Each item only stores
+// its `parent_id`, so the order of siblings is simply their order in the list.
+
+use crate::models::{Status, TodoItem};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// A task as it appears in the tree view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeRow {
+    /// Position of the task in the flat item list.
+    pub index: usize,
+    pub depth: usize,
+    pub has_children: bool,
+}
+
+/// Groups item positions by parent. Items whose parent no longer exists are treated as roots.
+fn children_by_parent(items: &[TodoItem]) -> HashMap<Option<Uuid>, Vec<usize>> {
+    let ids: HashSet<Uuid> = items.iter().map(|item| item.id).collect();
+    let mut children: HashMap<Option<Uuid>, Vec<usize>> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let parent = item.parent_id.filter(|parent| ids.contains(parent));
+        children.entry(parent).or_default().push(i);
+    }
+    children
+}
+
+/// Returns the rows to display in depth-first order, skipping the descendants of collapsed tasks.
+/// Tasks rejected by `shown` are skipped together with their subtasks.
+pub fn visible_rows(items: &[TodoItem], shown: impl Fn(&TodoItem) -> bool) -> Vec<TreeRow> {
+    let children = children_by_parent(items);
+    let mut rows = Vec::with_capacity(items.len());
+    let mut visited = vec![false; items.len()];
+
+    let roots = children.get(&None).into_iter().flatten().copied();
+    // Tasks caught in a parent cycle (only possible through hand edits) are never reached from a
+    // root, so they are walked as roots of their own instead of disappearing.
+    for root in roots.chain(0..items.len()) {
+        let mut stack = vec![(root, 0, false)];
+        while let Some((i, depth, hidden)) = stack.pop() {
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+            let hidden = hidden || !shown(&items[i]);
+            let kids = children.get(&Some(items[i].id));
+            if !hidden {
+                rows.push(TreeRow {
+                    index: i,
+                    depth,
+                    has_children: kids.is_some(),
+                });
+            }
+            let hide_kids = hidden || items[i].collapsed;
+            for &k in kids.into_iter().flatten().rev() {
+                stack.push((k, depth + 1, hide_kids));
+            }
+        }
+    }
+    rows
+}
+
+/// Returns the IDs of all tasks below the given one, at any depth.
+pub fn descendants(items: &[TodoItem], id: Uuid) -> Vec<Uuid> {
+    let children = children_by_parent(items);
+    let mut found = Vec::new();
+    let mut seen = HashSet::from([id]);
+    let mut stack = vec![id];
+    while let Some(parent) = stack.pop() {
+        for &i in children.get(&Some(parent)).into_iter().flatten() {
+            if seen.insert(items[i].id) {
+                found.push(items[i].id);
+                stack.push(items[i].id);
+            }
+        }
+    }
+    found
+}
+
+/// Counts the completed direct children of a task against all of its children that were not
+/// cancelled, e.g. `(3, 5)` for "3/5 done".
+pub fn child_progress(items: &[TodoItem], id: Uuid) -> (usize, usize) {
+    items
+        .iter()
+        .filter(|item| item.parent_id == Some(id) && item.status != Status::Cancelled)
+        .fold((0, 0), |(done, total), item| {
+            (
+                done + usize::from(item.status == Status::Completed),
+                total + 1,
+            )
+        })
+}
@@ -0,0 +1,105 @@
+// The backup.rs file keeps timestamped copies of the task file in a `backups` directory next to it,
+// e.g. `~/.taskmaster/backups/tasks-20261018-143000.json`, and rotates out the oldest ones. Backups
+// keep the extension of the task file, so each storage format only sees its own.
+
+use chrono::{Local, NaiveDateTime};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How many backups are kept unless `TASKMASTER_BACKUPS` says otherwise.
+pub const DEFAULT_BACKUP_COUNT: usize = 10;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// Local time the backup was taken, read from the file name.
+    pub created: NaiveDateTime,
+}
+
+/// Number of backups to keep, read from the `TASKMASTER_BACKUPS` environment variable. Zero turns
+/// backups off.
+pub fn backup_count() -> usize {
+    std::env::var("TASKMASTER_BACKUPS")
+        .ok()
+        .and_then(|count| count.trim().parse().ok())
+        .unwrap_or(DEFAULT_BACKUP_COUNT)
+}
+
+pub fn backup_dir(path: &Path) -> PathBuf {
+    path.with_file_name("backups")
+}
+
+// File name stem and extension shared by all backups of a task file, e.g. "tasks" and "json"
+fn backup_name_parts(path: &Path) -> (String, String) {
+    let part =
+        |part: Option<&std::ffi::OsStr>| part.unwrap_or_default().to_string_lossy().into_owned();
+    (part(path.file_stem()), part(path.extension()))
+}
+
+/// Copies the task file into the backup directory and removes the oldest backups so that at most
+/// `keep` remain. Returns the new backup, or `None` if there was no file or backups are off.
+pub fn create_backup(path: &Path, keep: usize) -> io::Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    create_backup_with(path, keep, |target| fs::copy(path, target).map(|_| ()))
+}
+
+/// Like `create_backup`, for storage that has to write its backups itself, e.g. a database that
+/// can't simply be copied while open. `write` is given the path to create.
+pub fn create_backup_with(
+    path: &Path,
+    keep: usize,
+    write: impl FnOnce(&Path) -> io::Result<()>,
+) -> io::Result<Option<PathBuf>> {
+    if keep == 0 {
+        return Ok(None);
+    }
+    let dir = backup_dir(path);
+    fs::create_dir_all(&dir)?;
+    let (stem, extension) = backup_name_parts(path);
+    let target = dir.join(format!(
+        "{}-{}.{}",
+        stem,
+        Local::now().format(TIMESTAMP_FORMAT),
+        extension
+    ));
+    // A backup from the same second is replaced
+    if target.exists() {
+        fs::remove_file(&target)?;
+    }
+    write(&target)?;
+
+    for old in list_backups(path)?.into_iter().skip(keep) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(Some(target))
+}
+
+/// The backups of the task file, newest first. Other files in the directory are ignored.
+pub fn list_backups(path: &Path) -> io::Result<Vec<Backup>> {
+    let dir = backup_dir(path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let (stem, extension) = backup_name_parts(path);
+    let prefix = format!("{}-", stem);
+    let suffix = format!(".{}", extension);
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let created = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(&suffix))
+            .and_then(|stamp| NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT).ok());
+        if let Some(created) = created {
+            backups.push(Backup { path, created });
+        }
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+    Ok(backups)
+}
@@ -0,0 +1,181 @@
+// The crypto.rs file encrypts task files with a key derived from a passphrase. The key is derived
+// with Argon2id and the contents are sealed with ChaCha20-Poly1305, so a wrong passphrase or a
+// tampered file is noticed instead of read as garbage. An encrypted file starts with a header
+// holding what is needed to derive the key again and decrypt it:
+//
+//   `TMCRYPT1` | memory cost, iterations, parallelism (u32 LE each) | salt (16) | nonce (12)
+//
+// followed by the encrypted JSON of the `TodoData`. The header is authenticated along with it.
+
+use crate::storage::file::{self, migration_backup_path, TodoData};
+use crate::storage::migrations::CURRENT_SCHEMA_VERSION;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"TMCRYPT1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 12 + SALT_LEN + NONCE_LEN;
+
+/// How hard the key is to derive: memory in KiB, iterations and parallelism. These are the Argon2
+/// defaults; files keep the costs they were written with, so they can be raised later.
+const COSTS: [u32; 3] = [
+    Params::DEFAULT_M_COST,
+    Params::DEFAULT_T_COST,
+    Params::DEFAULT_P_COST,
+];
+
+/// A key derived from a passphrase, together with the salt and costs it was derived with.
+#[derive(Clone)]
+pub struct Key {
+    key: chacha20poly1305::Key,
+    salt: [u8; SALT_LEN],
+    costs: [u32; 3],
+}
+
+impl Key {
+    /// Derives a key for newly encrypted files, with a fresh salt.
+    pub fn generate(passphrase: &str) -> io::Result<Self> {
+        let mut salt = [0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, salt, COSTS)
+    }
+
+    fn derive(passphrase: &str, salt: [u8; SALT_LEN], costs: [u32; 3]) -> io::Result<Self> {
+        let [memory, iterations, parallelism] = costs;
+        let params = Params::new(memory, iterations, parallelism, Some(32)).map_err(invalid)?;
+        let mut key = chacha20poly1305::Key::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(invalid)?;
+        Ok(Self { key, salt, costs })
+    }
+
+    /// Encrypts the contents of a file.
+    pub fn seal(&self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let mut sealed = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        sealed.extend_from_slice(MAGIC);
+        for cost in self.costs {
+            sealed.extend_from_slice(&cost.to_le_bytes());
+        }
+        sealed.extend_from_slice(&self.salt);
+        sealed.extend_from_slice(&nonce);
+        let ciphertext = ChaCha20Poly1305::new(&self.key)
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &sealed,
+                },
+            )
+            .map_err(|_| io::Error::other("Could not encrypt the tasks"))?;
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Decrypts the contents of a file sealed with this key.
+    pub fn open(&self, sealed: &[u8]) -> io::Result<Vec<u8>> {
+        let header = Header::read(sealed)?;
+        if header.salt != self.salt || header.costs != self.costs {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The file was encrypted with a different passphrase",
+            ));
+        }
+        ChaCha20Poly1305::new(&self.key)
+            .decrypt(
+                Nonce::from_slice(&header.nonce),
+                Payload {
+                    msg: &sealed[HEADER_LEN..],
+                    aad: &sealed[..HEADER_LEN],
+                },
+            )
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Wrong passphrase, or the file is damaged",
+                )
+            })
+    }
+}
+
+struct Header {
+    costs: [u32; 3],
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+}
+
+impl Header {
+    fn read(sealed: &[u8]) -> io::Result<Self> {
+        if sealed.len() < HEADER_LEN || !sealed.starts_with(MAGIC) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The file is not encrypted by TaskMaster",
+            ));
+        }
+        let field = |start: usize, len: usize| &sealed[MAGIC.len() + start..][..len];
+        let cost = |i: usize| u32::from_le_bytes(field(i * 4, 4).try_into().unwrap_or_default());
+        Ok(Self {
+            costs: [cost(0), cost(1), cost(2)],
+            salt: field(12, SALT_LEN).try_into().unwrap_or_default(),
+            nonce: field(12 + SALT_LEN, NONCE_LEN)
+                .try_into()
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Whether the file at `path` is an encrypted task file. A missing file is not.
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut start = [0; MAGIC.len()];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut start))
+        .is_ok_and(|_| &start == MAGIC)
+}
+
+/// Derives the key of the encrypted file at `path` from the passphrase, checking that it opens the
+/// file.
+pub fn unlock(path: &Path, passphrase: &str) -> io::Result<Key> {
+    let sealed = fs::read(path)?;
+    let header = Header::read(&sealed)?;
+    let key = Key::derive(passphrase, header.salt, header.costs)?;
+    key.open(&sealed)?;
+    Ok(key)
+}
+
+/// Reads an encrypted task file, like `file::load_from_file`. The copy kept from before a
+/// migration is encrypted as well.
+pub fn load(path: &Path, key: &Key) -> io::Result<TodoData> {
+    let sealed = match fs::read(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"))
+        }
+        result => result?,
+    };
+    let contents = key.open(&sealed)?;
+    let contents = String::from_utf8(contents).map_err(invalid)?;
+    let (todo_data, original_version) = file::parse(&contents)?;
+    if original_version < CURRENT_SCHEMA_VERSION {
+        let backup = migration_backup_path(path, original_version);
+        if !backup.exists() {
+            fs::write(&backup, &sealed)?;
+        }
+    }
+    Ok(todo_data)
+}
+
+/// Writes an encrypted task file, replacing it the same way as `file::save_to_file`.
+pub fn save(path: &Path, key: &Key, todo_data: &TodoData) -> io::Result<()> {
+    let sealed = key.seal(&serde_json::to_vec(todo_data)?)?;
+    file::replace_file(path, |writer| writer.write_all(&sealed))
+}
+
+fn invalid(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
@@ -0,0 +1,60 @@
+// The emergency.rs file keeps the tasks alive through a crash. When the app panics with unsaved
+// changes, the tasks it holds are dumped next to the task file, e.g. `tasks.json.recovery`, and the
+// next start offers to bring them back. The dump of an encrypted task file is encrypted with the
+// same key.
+
+use crate::models::TodoItem;
+use crate::storage::crypto::Key;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dump {
+    /// The list that was shown, `None` for the main list.
+    pub list: Option<String>,
+    pub written_at: DateTime<Utc>,
+    pub items: Vec<TodoItem>,
+}
+
+pub fn dump_path(main: &Path) -> PathBuf {
+    let mut name = main.file_name().unwrap_or_default().to_os_string();
+    name.push(".recovery");
+    main.with_file_name(name)
+}
+
+/// Writes the dump next to the main task file and returns where it went. It is written directly,
+/// without the care taken for the task file, as it only has to work once.
+pub fn write(main: &Path, dump: &Dump, key: Option<&Key>) -> io::Result<PathBuf> {
+    let path = dump_path(main);
+    let mut contents = serde_json::to_vec(dump)?;
+    if let Some(key) = key {
+        contents = key.seal(&contents)?;
+    }
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// The dump left by a crash, if there is one.
+pub fn read(main: &Path, key: Option<&Key>) -> io::Result<Option<Dump>> {
+    match fs::read(dump_path(main)) {
+        Ok(contents) => {
+            let contents = match key {
+                Some(key) => key.open(&contents)?,
+                None => contents,
+            };
+            Ok(Some(serde_json::from_slice(&contents)?))
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+pub fn discard(main: &Path) -> io::Result<()> {
+    match fs::remove_file(dump_path(main)) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,183 @@
+// The encrypted.rs file keeps the tasks in an encrypted task file, for tasks that must not be
+// readable by whoever gets hold of the disk or a backup. It works like the plain JSON file, except
+// that every change rewrites the whole file: a journal would hold the changed tasks unencrypted.
+// Backups are copies of the encrypted file. Stores are switched between plain and encrypted with
+// `encrypt_store` and `decrypt_store`, which also take care of the other copies of the tasks kept
+// next to a task file.
+
+use crate::models::TodoItem;
+use crate::storage::backup::{self, Backup};
+use crate::storage::crypto::{self, Key};
+use crate::storage::file::{self, load_from_file, save_to_file, TodoData};
+use crate::storage::task_file::TaskFile;
+use crate::storage::{journal, lists, lock, JsonFileStorage, Storage};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct EncryptedStorage {
+    file: TaskFile,
+    key: Key,
+}
+
+impl EncryptedStorage {
+    pub fn new(path: PathBuf, key: Key) -> Self {
+        Self {
+            file: TaskFile::new(path),
+            key,
+        }
+    }
+}
+
+impl Storage for EncryptedStorage {
+    fn describe(&self) -> String {
+        format!("{} (encrypted)", self.file.path.display())
+    }
+
+    fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+        let todo_data = crypto::load(&self.file.path, &self.key)?;
+        self.mark_seen();
+        Ok(todo_data.items)
+    }
+
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()> {
+        crypto::save(&self.file.path, &self.key, &TodoData::new(items.to_vec()))?;
+        self.mark_seen();
+        Ok(())
+    }
+
+    fn lock(&mut self) -> io::Result<bool> {
+        self.file.lock()
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.file.changed_externally()
+    }
+
+    fn mark_seen(&mut self) {
+        self.file.mark_seen()
+    }
+
+    fn backup(&mut self) -> io::Result<()> {
+        self.file.backup(false)
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        self.file.list_backups()
+    }
+
+    fn restore_backup(&mut self, chosen: &Backup) -> io::Result<Vec<TodoItem>> {
+        let todo_data = crypto::load(&chosen.path, &self.key)?;
+        self.file.backup(true)?;
+        Ok(todo_data.items)
+    }
+
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        self.file.list_names()
+    }
+
+    fn open_list(&self, name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Ok(Box::new(Self {
+            file: self.file.list(name),
+            key: self.key.clone(),
+        }))
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        vec![self.file.path.clone()]
+    }
+
+    fn is_encrypted(&self) -> bool {
+        true
+    }
+}
+
+/// Encrypts the plain task file at `main`, its other lists, their backups and the copies next to
+/// them with `key`. Missing task files are created empty, so everything saved from now on is
+/// encrypted. Returns the copies next to the task files, see `side_files`.
+pub fn encrypt_store(main: &Path, key: &Key) -> io::Result<Vec<PathBuf>> {
+    let mut copies = Vec::new();
+    for path in store_files(main)? {
+        for side_file in side_files(&path)? {
+            if !crypto::is_encrypted(&side_file) {
+                let sealed = key.seal(&fs::read(&side_file)?)?;
+                file::replace_file(&side_file, |writer| writer.write_all(&sealed))?;
+            }
+            copies.push(side_file);
+        }
+        if crypto::is_encrypted(&path) {
+            continue;
+        }
+        let items = match JsonFileStorage::new(path.clone()).load() {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            result => result?,
+        };
+        crypto::save(&path, key, &TodoData::new(items))?;
+        // The journal was folded into the file above
+        journal::clear(&path)?;
+        for old in backup::list_backups(&path)? {
+            if !crypto::is_encrypted(&old.path) {
+                crypto::save(&old.path, key, &load_from_file(&old.path)?)?;
+            }
+        }
+    }
+    Ok(copies)
+}
+
+/// Turns the encrypted task file at `main`, its other lists, their backups and the copies next to
+/// them back into plain JSON.
+pub fn decrypt_store(main: &Path, key: &Key) -> io::Result<()> {
+    for path in store_files(main)? {
+        for side_file in side_files(&path)? {
+            if crypto::is_encrypted(&side_file) {
+                let contents = key.open(&fs::read(&side_file)?)?;
+                file::replace_file(&side_file, |writer| writer.write_all(&contents))?;
+            }
+        }
+        let backups = backup::list_backups(&path)?.into_iter().map(|old| old.path);
+        for file in std::iter::once(path.clone()).chain(backups) {
+            if crypto::is_encrypted(&file) {
+                save_to_file(&file, &crypto::load(&file, key)?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The main task file and those of the other lists
+fn store_files(main: &Path) -> io::Result<Vec<PathBuf>> {
+    let names = lists::list_names(main)?;
+    Ok(std::iter::once(main.to_path_buf())
+        .chain(names.iter().map(|name| lists::list_path(main, Some(name))))
+        .collect())
+}
+
+// The copies of the tasks kept next to a task file, named after it: e.g. `tasks.json.v0.bak` from
+// before a migration, `tasks.json.corrupt-20261018-143000` from a damaged file,
+// `tasks.json.imported` from the move to SQLite and `tasks.json.recovery` from a crash. The lock
+// and journal hold no tasks of their own.
+fn side_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut prefix = path.file_name().unwrap_or_default().to_os_string();
+    prefix.push(".");
+    let prefix = prefix.to_string_lossy().into_owned();
+    let skipped = [lock::lock_path(path), journal::journal_path(path)];
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let mut side_files = Vec::new();
+    for entry in entries {
+        let side_file = path.with_file_name(entry?.file_name());
+        let name = side_file.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with(&prefix) && side_file.is_file() && !skipped.contains(&side_file) {
+            side_files.push(side_file);
+        }
+    }
+    side_files.sort();
+    Ok(side_files)
+}
@@ -1,26 +1,74 @@
 use crate::models::TodoItem;
+use crate::storage::migrations::{self, CURRENT_SCHEMA_VERSION};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::io::{self};
-use std::path::Path;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tempfile::NamedTempFile;
+use uuid::Uuid;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct TodoData {
+    pub schema_version: u32,
     pub items: Vec<TodoItem>,
 }
 
-// Save the TodoData to a file at the given path
+impl TodoData {
+    pub fn new(items: Vec<TodoItem>) -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            items,
+        }
+    }
+
+    // Give a fresh ID to any item whose ID was already seen, e.g. after copying an entry by hand
+    pub fn ensure_unique_ids(&mut self) {
+        let mut seen = HashSet::new();
+        for item in &mut self.items {
+            if !seen.insert(item.id) {
+                item.id = Uuid::new_v4();
+                seen.insert(item.id);
+            }
+        }
+    }
+}
+
+// Save the TodoData to a file at the given path. The data is written to a temporary file in the
+// same directory and renamed over the old file, so a crash or full disk mid-write leaves the
+// previous version intact.
 pub fn save_to_file(path: &Path, todo_data: &TodoData) -> io::Result<()> {
+    // Write the TodoData in pretty JSON format
+    replace_file(path, |writer| {
+        Ok(serde_json::to_writer_pretty(writer, todo_data)?)
+    })
+}
+
+/// Replaces the file at `path` with what `write` writes, through a temporary file like
+/// `save_to_file`.
+pub fn replace_file(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> io::Result<()> {
     // Ensure the parent directory exists
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?; // Create the directory if it doesn't exist
-    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?; // Create the directory if it doesn't exist
 
-    // Open the file for writing
-    let file = fs::File::create(path)?;
+    let mut temp_file = NamedTempFile::new_in(parent)?;
+    let mut writer = BufWriter::new(temp_file.as_file_mut());
+    write(&mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    temp_file.as_file().sync_all()?;
 
-    // Write the TodoData to the file in pretty JSON format
-    serde_json::to_writer_pretty(file, todo_data)?;
+    // Swap it in and make sure the rename itself reaches the disk
+    temp_file.persist(path).map_err(|error| error.error)?;
+    #[cfg(unix)]
+    fs::File::open(parent)?.sync_all()?;
 
     Ok(())
 }
@@ -32,8 +80,14 @@ pub fn load_from_file(path: &Path) -> io::Result<TodoData> {
         // Read the file content as a string
         let file_content = fs::read_to_string(path)?;
 
-        // Deserialize the JSON string into TodoData
-        let todo_data: TodoData = serde_json::from_str(&file_content)?;
+        // Bring files from older versions up to date, keeping a copy of the original
+        let (todo_data, original_version) = parse(&file_content)?;
+        if original_version < CURRENT_SCHEMA_VERSION {
+            let backup = migration_backup_path(path, original_version);
+            if !backup.exists() {
+                fs::write(&backup, &file_content)?;
+            }
+        }
 
         Ok(todo_data)
     } else {
@@ -41,3 +95,36 @@ pub fn load_from_file(path: &Path) -> io::Result<TodoData> {
         Err(io::Error::new(io::ErrorKind::NotFound, "File not found"))
     }
 }
+
+/// Reads TodoData from the contents of a task file, migrating it if it was written by an older
+/// version. Also returns the schema version the contents had.
+pub fn parse(file_content: &str) -> io::Result<(TodoData, u32)> {
+    let mut raw: serde_json::Value = serde_json::from_str(file_content)?;
+    let original_version = migrations::migrate(&mut raw)?;
+
+    // Deserialize the JSON into TodoData. Files that needed no migration are read from the text
+    // again so errors point at a line and column.
+    let mut todo_data: TodoData = if original_version == CURRENT_SCHEMA_VERSION {
+        serde_json::from_str(file_content)?
+    } else {
+        serde_json::from_value(raw)?
+    };
+    todo_data.ensure_unique_ids();
+
+    Ok((todo_data, original_version))
+}
+
+/// Where the pre-migration copy of a file is kept, e.g. `tasks.json.v0.bak`. An existing backup
+/// is never replaced, so it always holds the file as the older version left it.
+pub fn migration_backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// When the file was last modified, or `None` if it doesn't exist.
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
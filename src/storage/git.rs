@@ -0,0 +1,368 @@
+// The git.rs file keeps the history of the tasks when the directory of the task file is a git
+// repository. Every change is committed with a message saying what happened, e.g.
+// "complete: Security approval", and past versions of a list can be read back from the commits.
+// Pushing and pulling are left to the user; this only uses the local repository, through the `git`
+// command.
+
+use crate::models::{Status, TodoItem};
+use crate::storage::backup::Backup;
+use crate::storage::location;
+use crate::storage::recovery::Salvage;
+use crate::storage::Storage;
+use chrono::{DateTime, Local};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::rc::Rc;
+use uuid::Uuid;
+
+/// Author used for the commits when git doesn't know who the user is.
+const FALLBACK_IDENTITY: [&str; 4] = [
+    "-c",
+    "user.name=TaskMaster",
+    "-c",
+    "user.email=taskmaster@localhost",
+];
+
+/// A commit that changed a task list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revision {
+    pub id: String,
+    pub committed: DateTime<Local>,
+    pub message: String,
+}
+
+impl Revision {
+    /// The abbreviated commit hash, as git shows it.
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+}
+
+/// Opens the storage for a task file of the same kind as the one being versioned, so a past
+/// version of it can be read.
+pub type Opener = Rc<dyn Fn(PathBuf) -> io::Result<Box<dyn Storage>>>;
+
+/// Whether the tasks in `dir` should be versioned, which is when it is the top of a git repository.
+pub fn is_repository(dir: &Path) -> bool {
+    dir.join(".git").exists()
+}
+
+pub struct GitStorage {
+    inner: Box<dyn Storage>,
+    // The top of the repository, which is the directory of the main task file
+    repository: PathBuf,
+    open: Opener,
+    // Whether the commits need `FALLBACK_IDENTITY`
+    anonymous: bool,
+    // The tasks as last read or written, to tell what a change did
+    known: Vec<TodoItem>,
+}
+
+impl GitStorage {
+    /// Versions the tasks kept by `inner` in the repository at `repository`. If the repository has
+    /// no `.gitignore`, one is added in a commit of its own, so the files that don't belong in the
+    /// history, like locks and backups, don't show up as untracked.
+    pub fn open(inner: Box<dyn Storage>, repository: PathBuf, open: Opener) -> io::Result<Self> {
+        let anonymous = !git(&repository, &["config", "user.email"])?
+            .status
+            .success();
+        let storage = Self {
+            inner,
+            repository,
+            open,
+            anonymous,
+            known: Vec::new(),
+        };
+        if !storage.repository.join(".gitignore").exists() {
+            fs::write(storage.repository.join(".gitignore"), location::GITIGNORE)?;
+            storage.commit_paths(
+                "ignore TaskMaster's locks, backups and recovery files",
+                &[".gitignore".to_string()],
+            )?;
+        }
+        Ok(storage)
+    }
+
+    // The files of this list as git paths, relative to the top of the repository
+    fn tracked_paths(&self) -> Vec<String> {
+        self.inner
+            .files()
+            .iter()
+            .map(|file| {
+                let relative = file.strip_prefix(&self.repository).unwrap_or(file);
+                relative
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .collect()
+    }
+
+    // Commits the files of this list, if they changed. Encrypted tasks are committed without their
+    // names, e.g. "complete" instead of "complete: Security approval".
+    fn commit(&mut self, message: &str) -> io::Result<()> {
+        let message = match message.split_once(':') {
+            Some((verb, _)) if self.inner.is_encrypted() => verb,
+            _ => message,
+        };
+        self.commit_paths(message, &self.tracked_paths())
+            .map_err(|error| io::Error::new(error.kind(), format!("Saved, but {}", error)))
+    }
+
+    // Commits `paths` as they are in the working tree, if they changed. Whatever else the user has
+    // staged is left out of the commit and stays staged. The repository's hooks run as for any
+    // other commit.
+    fn commit_paths(&self, message: &str, paths: &[String]) -> io::Result<()> {
+        let status = run(
+            &self.repository,
+            &args(&["status", "--porcelain", "--"], paths),
+        )?;
+        if status.stdout.is_empty() {
+            return Ok(());
+        }
+        // New files have to be known to git before they can be committed by path, and files that
+        // are gone can only be committed if git knew them
+        let (present, missing): (Vec<_>, Vec<_>) = paths
+            .iter()
+            .partition(|path| self.repository.join(path).exists());
+        if !present.is_empty() {
+            run(&self.repository, &args(&["add", "--"], &present))?;
+        }
+        let mut known = present;
+        if !missing.is_empty() {
+            let listed = run(&self.repository, &args(&["ls-files", "--"], &missing))?;
+            let listed = String::from_utf8_lossy(&listed.stdout);
+            known.extend(
+                missing
+                    .into_iter()
+                    .filter(|path| listed.lines().any(|line| line == *path)),
+            );
+        }
+        let identity: &[&str] = if self.anonymous {
+            &FALLBACK_IDENTITY
+        } else {
+            &[]
+        };
+        let mut commit = identity.to_vec();
+        commit.extend(["commit", "-q", "--only", "-m", message, "--"]);
+        run(&self.repository, &args(&commit, &known)).map(|_| ())
+    }
+}
+
+impl Storage for GitStorage {
+    fn describe(&self) -> String {
+        self.inner.describe()
+    }
+
+    fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+        let items = self.inner.load()?;
+        self.known = items.clone();
+        Ok(items)
+    }
+
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()> {
+        self.inner.save(items)?;
+        let message = describe_changes(&self.known, items);
+        self.known = items.to_vec();
+        self.commit(&message)
+    }
+
+    fn upsert(&mut self, item: &TodoItem) -> io::Result<()> {
+        self.inner.upsert(item)?;
+        let message = match self.known.iter_mut().find(|known| known.id == item.id) {
+            Some(known) => {
+                let message = describe_change(known, item);
+                *known = item.clone();
+                message
+            }
+            None => {
+                self.known.push(item.clone());
+                format!("add: {}", item.todo)
+            }
+        };
+        self.commit(&message)
+    }
+
+    fn delete(&mut self, id: Uuid) -> io::Result<()> {
+        self.inner.delete(id)?;
+        let name = self
+            .known
+            .iter()
+            .find(|known| known.id == id)
+            .map_or_else(|| id.to_string(), |known| known.todo.clone());
+        self.known.retain(|known| known.id != id);
+        self.commit(&format!("delete: {}", name))
+    }
+
+    fn lock(&mut self) -> io::Result<bool> {
+        self.inner.lock()
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.inner.changed_externally()
+    }
+
+    fn mark_seen(&mut self) {
+        self.inner.mark_seen()
+    }
+
+    fn backup(&mut self) -> io::Result<()> {
+        self.inner.backup()
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        self.inner.list_backups()
+    }
+
+    fn restore_backup(&mut self, backup: &Backup) -> io::Result<Vec<TodoItem>> {
+        self.inner.restore_backup(backup)
+    }
+
+    fn salvage(&self) -> io::Result<Salvage> {
+        self.inner.salvage()
+    }
+
+    fn quarantine(&mut self) -> io::Result<String> {
+        self.inner.quarantine()
+    }
+
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        self.inner.list_names()
+    }
+
+    fn open_list(&self, name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Ok(Box::new(Self {
+            inner: self.inner.open_list(name)?,
+            repository: self.repository.clone(),
+            open: Rc::clone(&self.open),
+            anonymous: self.anonymous,
+            known: Vec::new(),
+        }))
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        self.inner.files()
+    }
+
+    fn is_encrypted(&self) -> bool {
+        self.inner.is_encrypted()
+    }
+
+    fn history(&self) -> io::Result<Vec<Revision>> {
+        // `git log` fails before the first commit
+        let head = ["rev-parse", "--verify", "-q", "HEAD"];
+        if !git(&self.repository, &head)?.status.success() {
+            return Ok(Vec::new());
+        }
+        let format = ["log", "--format=%H%x09%ct%x09%s", "--"];
+        let output = run(&self.repository, &args(&format, &self.tracked_paths()))?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let id = fields.next()?.to_string();
+                let committed = DateTime::from_timestamp(fields.next()?.parse().ok()?, 0)?;
+                Some(Revision {
+                    id,
+                    committed: committed.with_timezone(&Local),
+                    message: fields.next().unwrap_or_default().to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn load_revision(&self, revision: &Revision) -> io::Result<Vec<TodoItem>> {
+        // The files are copied out of the commit and read like the current ones
+        let copy = tempfile::tempdir()?;
+        let files = self.inner.files();
+        for (file, path) in files.iter().zip(self.tracked_paths()) {
+            let output = git(
+                &self.repository,
+                &["show", &format!("{}:{}", revision.id, path)],
+            )?;
+            // Not every file exists in every commit, e.g. an empty journal
+            if output.status.success() {
+                fs::write(
+                    copy.path().join(file.file_name().unwrap_or_default()),
+                    output.stdout,
+                )?;
+            }
+        }
+        let main = files
+            .first()
+            .and_then(|file| file.file_name())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::Unsupported, "This storage keeps no files")
+            })?;
+        (self.open)(copy.path().join(main))?.load()
+    }
+}
+
+// Describes what saving `after` over `before` changed, e.g. "complete: Security approval" for a
+// single task or "update 3 tasks"
+fn describe_changes(before: &[TodoItem], after: &[TodoItem]) -> String {
+    let mut changes: Vec<String> = after
+        .iter()
+        .filter_map(
+            |item| match before.iter().find(|known| known.id == item.id) {
+                Some(known) if known == item => None,
+                Some(known) => Some(describe_change(known, item)),
+                None => Some(format!("add: {}", item.todo)),
+            },
+        )
+        .collect();
+    changes.extend(
+        before
+            .iter()
+            .filter(|known| !after.iter().any(|item| item.id == known.id))
+            .map(|known| format!("delete: {}", known.todo)),
+    );
+    match changes.len() {
+        0 => "save tasks".to_string(),
+        1 => changes.remove(0),
+        count => format!("update {} tasks", count),
+    }
+}
+
+fn describe_change(before: &TodoItem, after: &TodoItem) -> String {
+    let verb = match after.status {
+        status if status == before.status => "edit",
+        Status::Completed => "complete",
+        Status::Cancelled => "cancel",
+        _ if before.status.is_closed() => "reopen",
+        _ => "update",
+    };
+    format!("{}: {}", verb, after.todo)
+}
+
+fn args<'a>(first: &[&'a str], rest: &'a [impl AsRef<str>]) -> Vec<&'a str> {
+    first
+        .iter()
+        .copied()
+        .chain(rest.iter().map(AsRef::as_ref))
+        .collect()
+}
+
+fn git(repository: &Path, args: &[&str]) -> io::Result<Output> {
+    Command::new("git")
+        .arg("-C")
+        .arg(repository)
+        .args(args)
+        .output()
+}
+
+// Runs git and turns a failure into an error carrying what git said
+fn run(repository: &Path, args: &[&str]) -> io::Result<Output> {
+    let output = git(repository, args)?;
+    if output.status.success() {
+        return Ok(output);
+    }
+    Err(io::Error::other(format!(
+        "git {} failed: {}",
+        args.first().unwrap_or(&""),
+        String::from_utf8_lossy(&output.stderr).trim()
+    )))
+}
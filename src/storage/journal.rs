@@ -0,0 +1,141 @@
+// The journal.rs file records changes to a task file as they happen, one JSON line per change in
+// e.g. `tasks.json.journal`, instead of rewriting the whole file each time. Loading replays the
+// journal on top of the task file; a full save writes the task file and starts the journal over.
+
+use crate::models::TodoItem;
+use crate::storage::migrations::{self, CURRENT_SCHEMA_VERSION};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// How many changes the journal collects before it is folded into the task file.
+pub const COMPACT_AFTER: usize = 200;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: DateTime<Utc>,
+    /// The schema version the entry was written with, so older entries can be migrated.
+    #[serde(rename = "v")]
+    pub schema_version: u32,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// A task was added or changed; it holds the task as it is now.
+    Upsert(Value),
+    Delete(Uuid),
+}
+
+impl Entry {
+    pub fn upsert(item: &TodoItem) -> io::Result<Self> {
+        Ok(Self::new(Change::Upsert(serde_json::to_value(item)?)))
+    }
+
+    pub fn delete(id: Uuid) -> Self {
+        Self::new(Change::Delete(id))
+    }
+
+    fn new(change: Change) -> Self {
+        Self {
+            at: Utc::now(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            change,
+        }
+    }
+}
+
+pub fn journal_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".journal");
+    path.with_file_name(name)
+}
+
+/// Adds an entry to the end of the journal and waits for it to reach the disk.
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(journal_path(path))?;
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // An entry cut off by a crash must not run into the next one
+    if file.metadata()?.len() > 0 {
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            line.insert(0, '\n');
+        }
+    }
+    file.write_all(line.as_bytes())?;
+    file.sync_data()
+}
+
+/// Reads the journal, oldest entry first. Lines that can't be read, like an entry cut off by a
+/// crash, are skipped. Entries written by a newer TaskMaster fail the read with `Unsupported`.
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    let file = match File::open(journal_path(path)) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let Ok(entry) = serde_json::from_str::<Entry>(&line?) else {
+            continue;
+        };
+        if entry.schema_version > CURRENT_SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "The change journal was written with schema version {}, but this version of \
+                     TaskMaster only understands up to version {}.",
+                    entry.schema_version, CURRENT_SCHEMA_VERSION
+                ),
+            ));
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Applies the entries to the tasks in order. A changed task keeps its place; a new one is added
+/// at the end.
+pub fn replay(items: &mut Vec<TodoItem>, entries: Vec<Entry>) -> io::Result<()> {
+    for entry in entries {
+        match entry.change {
+            Change::Upsert(value) => {
+                let mut data = json!({ "schema_version": entry.schema_version, "items": [value] });
+                migrations::migrate(&mut data)?;
+                let item: TodoItem = serde_json::from_value(data["items"][0].take())?;
+                match items.iter_mut().find(|stored| stored.id == item.id) {
+                    Some(stored) => *stored = item,
+                    None => items.push(item),
+                }
+            }
+            Change::Delete(id) => items.retain(|stored| stored.id != id),
+        }
+    }
+    Ok(())
+}
+
+/// Starts the journal over, once everything in it is part of the task file.
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(journal_path(path)) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
+
+/// The size of the journal in bytes, which changes with every entry added.
+pub fn len(path: &Path) -> u64 {
+    fs::metadata(journal_path(path)).map_or(0, |metadata| metadata.len())
+}
@@ -0,0 +1,170 @@
+// The json.rs file keeps the tasks in a single JSON file, by default `tasks.json` in the data directory.
+// Besides reading and writing it, it salvages damaged files; the lock, noticing outside edits and
+// backups come with `TaskFile`. Changes to single tasks go to a journal next to the file, which is
+// folded into it on the next full save.
+
+use crate::models::TodoItem;
+use crate::storage::backup::Backup;
+use crate::storage::file::{load_from_file, save_to_file, TodoData};
+use crate::storage::journal::{self, Entry};
+use crate::storage::recovery::{self, Salvage};
+use crate::storage::task_file::TaskFile;
+use crate::storage::Storage;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+pub struct JsonFileStorage {
+    file: TaskFile,
+    // Size of the journal when it was last read or written here
+    journal_len: u64,
+    // Entries in the journal, to know when to fold it into the file
+    journal_entries: usize,
+}
+
+impl JsonFileStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self::from_file(TaskFile::new(path))
+    }
+
+    fn from_file(file: TaskFile) -> Self {
+        Self {
+            file,
+            journal_len: 0,
+            journal_entries: 0,
+        }
+    }
+
+    // Appends a change to the journal, folding the journal into the file once it has grown long
+    fn record(&mut self, entry: Entry) -> io::Result<()> {
+        // Like any update, it needs stored tasks to apply to
+        if !self.file.path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Nothing stored yet",
+            ));
+        }
+        journal::append(&self.file.path, &entry)?;
+        self.journal_entries += 1;
+        self.journal_len = journal::len(&self.file.path);
+        if self.journal_entries >= journal::COMPACT_AFTER {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    // Writes the file with the journal applied and starts the journal over
+    fn compact(&mut self) -> io::Result<()> {
+        let items = self.load()?;
+        self.save(&items)
+    }
+
+    // Makes the file itself complete before it is copied, as long as this process may write it
+    fn compact_before_copy(&mut self) -> io::Result<()> {
+        if self.file.is_locked() && journal::len(&self.file.path) > 0 {
+            self.compact()?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn describe(&self) -> String {
+        self.file.path.display().to_string()
+    }
+
+    fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+        let mut todo_data = load_from_file(&self.file.path)?;
+        let entries = journal::read(&self.file.path)?;
+        self.journal_entries = entries.len();
+        journal::replay(&mut todo_data.items, entries)?;
+        self.mark_seen();
+        Ok(todo_data.items)
+    }
+
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()> {
+        // Once the file is written, everything in the journal is part of it
+        save_to_file(&self.file.path, &TodoData::new(items.to_vec()))?;
+        journal::clear(&self.file.path)?;
+        self.journal_entries = 0;
+        self.mark_seen();
+        Ok(())
+    }
+
+    fn upsert(&mut self, item: &TodoItem) -> io::Result<()> {
+        self.record(Entry::upsert(item)?)
+    }
+
+    fn delete(&mut self, id: Uuid) -> io::Result<()> {
+        self.record(Entry::delete(id))
+    }
+
+    fn lock(&mut self) -> io::Result<bool> {
+        self.file.lock()
+    }
+
+    fn changed_externally(&self) -> bool {
+        self.file.changed_externally()
+            || (self.file.path.exists() && journal::len(&self.file.path) != self.journal_len)
+    }
+
+    fn mark_seen(&mut self) {
+        self.file.mark_seen();
+        self.journal_len = journal::len(&self.file.path);
+    }
+
+    fn backup(&mut self) -> io::Result<()> {
+        self.compact_before_copy()?;
+        self.file.backup(false)
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        self.file.list_backups()
+    }
+
+    fn restore_backup(&mut self, chosen: &Backup) -> io::Result<Vec<TodoItem>> {
+        let todo_data = load_from_file(&chosen.path)?;
+        self.compact_before_copy()?;
+        self.file.backup(true)?;
+        Ok(todo_data.items)
+    }
+
+    fn salvage(&self) -> io::Result<Salvage> {
+        let contents = fs::read(&self.file.path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default();
+        let mut salvage = recovery::salvage(&contents);
+        if journal::journal_path(&self.file.path).exists() {
+            let _ = journal::read(&self.file.path)
+                .and_then(|entries| journal::replay(&mut salvage.items, entries));
+        }
+        Ok(salvage)
+    }
+
+    fn quarantine(&mut self) -> io::Result<String> {
+        let moved_to = recovery::quarantine(&self.file.path)?;
+        // The journal would be applied to whatever is saved next, so it goes aside as well. Its
+        // changes were carried over into the salvaged tasks.
+        let journal_path = journal::journal_path(&self.file.path);
+        if journal_path.exists() {
+            recovery::quarantine(&journal_path)?;
+        }
+        Ok(moved_to.display().to_string())
+    }
+
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        self.file.list_names()
+    }
+
+    fn open_list(&self, name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Ok(Box::new(Self::from_file(self.file.list(name))))
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        vec![
+            self.file.path.clone(),
+            journal::journal_path(&self.file.path),
+        ]
+    }
+}
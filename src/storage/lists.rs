@@ -0,0 +1,61 @@
+// The lists.rs file places the named task lists. The main list is the task file itself; every
+// other list is a file of the same kind in a `lists` directory next to it, e.g. `lists/Work.json`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What the main list is called in the app.
+pub const MAIN_LIST_NAME: &str = "Inbox";
+
+/// The name shown for a list, `None` being the main list.
+pub fn label(name: Option<&str>) -> &str {
+    name.unwrap_or(MAIN_LIST_NAME)
+}
+
+/// Whether a list can be called `name`. Names become file names, so they are kept to letters,
+/// digits, spaces, `-` and `_`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && name.trim() == name
+        && !name.eq_ignore_ascii_case(MAIN_LIST_NAME)
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+}
+
+/// Where the list called `name` is kept, given the path of the main list.
+pub fn list_path(main: &Path, name: Option<&str>) -> PathBuf {
+    let Some(name) = name else {
+        return main.to_path_buf();
+    };
+    let mut file_name = name.to_string();
+    if let Some(extension) = main.extension() {
+        file_name.push('.');
+        file_name.push_str(&extension.to_string_lossy());
+    }
+    main.with_file_name("lists").join(file_name)
+}
+
+/// The names of the lists next to the main list, sorted.
+pub fn list_names(main: &Path) -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(main.with_file_name("lists")) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_file() || path.extension() != main.extension() {
+            continue;
+        }
+        if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+            if is_valid_name(name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort_by_key(|name| name.to_lowercase());
+    Ok(names)
+}
@@ -0,0 +1,133 @@
+// The location.rs file decides where the task file lives. In order, the `--file` flag, the
+// `TASKMASTER_FILE` environment variable, a project's `.taskmaster/tasks.json` found in the current
+// directory or above it, and the config file can name it; otherwise it is `tasks.json` in the
+// platform's data directory (e.g. `~/.local/share/taskmaster` on Linux). Older versions kept
+// everything in `~/.taskmaster`, which is moved over on first start.
+
+use crate::config::{project_dirs, Config};
+use directories::BaseDirs;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory holding a project's tasks, inside the project's top directory.
+pub const PROJECT_DIR: &str = ".taskmaster";
+
+/// What git should leave alone next to the task files: locks, backups, damaged files moved aside
+/// and crash dumps.
+pub const GITIGNORE: &str = "*.lock\nbackups/\n*.corrupt-*\n*.bak\n*.recovery\n*.db-journal\n";
+
+/// The task file to open. `flag` is the path given with `--file` and `project` the task file of
+/// the project being worked in, if any. Also returns where the data was moved from when it was
+/// found at the legacy location.
+pub fn resolve(
+    flag: Option<PathBuf>,
+    project: Option<PathBuf>,
+    config: &Config,
+) -> io::Result<(PathBuf, Option<PathBuf>)> {
+    let named = flag
+        .or_else(|| {
+            env::var_os("TASKMASTER_FILE")
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        })
+        .or(project)
+        .or_else(|| config.file.clone());
+    if let Some(path) = named {
+        return Ok((path, None));
+    }
+
+    let data_dir = project_dirs()
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "Could not determine a data directory for the task file. Pass --file or set \
+                 TASKMASTER_FILE to choose one.",
+            )
+        })?;
+    let moved_from = match legacy_dir() {
+        Some(legacy) if move_legacy_data(&legacy, &data_dir)? => Some(legacy),
+        _ => None,
+    };
+    Ok((data_dir.join("tasks.json"), moved_from))
+}
+
+/// The project task file in `start` or the closest directory above it that has one, the way git
+/// finds its repository. The home directory is skipped, as `~/.taskmaster` is where older versions
+/// kept the personal tasks.
+pub fn find_project_file(start: &Path, home: Option<&Path>) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .filter(|dir| Some(*dir) != home)
+        .map(project_file)
+        .find(|path| path.exists() || path.with_extension("db").exists())
+}
+
+/// Where the task file of a project with the given top directory goes.
+pub fn project_file(dir: &Path) -> PathBuf {
+    dir.join(PROJECT_DIR).join("tasks.json")
+}
+
+/// Sets up the directory for a project's tasks in `dir` and returns the task file to create. Lock
+/// files and backups are kept out of version control. Fails with `AlreadyExists` if the project
+/// already has tasks.
+pub fn init_project(dir: &Path) -> io::Result<PathBuf> {
+    let path = project_file(dir);
+    if path.exists() || path.with_extension("db").exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    let project_dir = dir.join(PROJECT_DIR);
+    fs::create_dir_all(&project_dir)?;
+    let gitignore = project_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, GITIGNORE)?;
+    }
+    Ok(path)
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf())
+}
+
+/// Where older versions kept the task file and its backups.
+pub fn legacy_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".taskmaster"))
+}
+
+/// Moves everything in `legacy` into `data_dir`, unless `data_dir` already holds tasks. Returns
+/// whether anything was moved.
+pub fn move_legacy_data(legacy: &Path, data_dir: &Path) -> io::Result<bool> {
+    let has_tasks = |dir: &Path| dir.join("tasks.json").exists() || dir.join("tasks.db").exists();
+    if legacy == data_dir || !has_tasks(legacy) || has_tasks(data_dir) {
+        return Ok(false);
+    }
+    fs::create_dir_all(data_dir)?;
+    move_entries(legacy, data_dir)?;
+    fs::remove_dir(legacy)?;
+    Ok(true)
+}
+
+// Renames, or copies and deletes where a rename can't cross file systems
+fn move_entries(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if fs::rename(entry.path(), &target).is_ok() {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target)?;
+            move_entries(&entry.path(), &target)?;
+            fs::remove_dir(entry.path())?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
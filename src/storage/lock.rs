@@ -0,0 +1,32 @@
+// The lock.rs file marks a task file as in use, so a second TaskMaster on the same file knows to
+// keep its hands off. The lock is advisory and lives on a `.lock` file next to the task file, which
+// the operating system releases when the process exits, even after a crash.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Takes the lock on the task file. Returns `Ok(None)` if another process holds it. The lock is
+/// held until the returned file is dropped.
+pub fn try_lock(path: &Path) -> io::Result<Option<File>> {
+    let lock_path = lock_path(path);
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(error)) => Err(error),
+    }
+}
@@ -0,0 +1,70 @@
+// The memory.rs file keeps the tasks in memory only. Tests run the app on it so nothing they do
+// touches the user's task file.
+
+use crate::models::TodoItem;
+use crate::storage::Storage;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::rc::Rc;
+use uuid::Uuid;
+
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    // The list this storage holds, `None` for the main list
+    list: Option<String>,
+    // All lists, shared with the storages opened from this one. A list is missing until its first
+    // save, so loading it behaves like a missing file.
+    lists: Rc<RefCell<BTreeMap<Option<String>, Vec<TodoItem>>>>,
+}
+
+impl MemoryStorage {
+    fn with_stored_items<T>(&mut self, f: impl FnOnce(&mut Vec<TodoItem>) -> T) -> io::Result<T> {
+        self.lists
+            .borrow_mut()
+            .get_mut(&self.list)
+            .map(f)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Nothing stored yet"))
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn describe(&self) -> String {
+        "memory".to_string()
+    }
+
+    fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+        self.with_stored_items(|items| items.clone())
+    }
+
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()> {
+        self.lists
+            .borrow_mut()
+            .insert(self.list.clone(), items.to_vec());
+        Ok(())
+    }
+
+    fn upsert(&mut self, item: &TodoItem) -> io::Result<()> {
+        self.with_stored_items(
+            |items| match items.iter_mut().find(|stored| stored.id == item.id) {
+                Some(stored) => *stored = item.clone(),
+                None => items.push(item.clone()),
+            },
+        )
+    }
+
+    fn delete(&mut self, id: Uuid) -> io::Result<()> {
+        self.with_stored_items(|items| items.retain(|stored| stored.id != id))
+    }
+
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        Ok(self.lists.borrow().keys().flatten().cloned().collect())
+    }
+
+    fn open_list(&self, name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Ok(Box::new(Self {
+            list: name.map(str::to_string),
+            lists: Rc::clone(&self.lists),
+        }))
+    }
+}
@@ -0,0 +1,71 @@
+// The migrations.rs file upgrades task files written by older versions of TaskMaster. Each step
+// takes the raw JSON of one schema version to the next, so a file of any older version is brought
+// up to date by running the remaining steps in order before it is deserialized.
+
+use serde_json::Value;
+use std::io;
+use uuid::Uuid;
+
+/// The schema version written by this build.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// `MIGRATIONS[n]` upgrades a file from version `n` to `n + 1`
+const MIGRATIONS: [Migration; CURRENT_SCHEMA_VERSION as usize] = [v0_to_v1];
+
+/// The schema version of a file. Files written before versioning was introduced count as 0.
+pub fn schema_version(data: &Value) -> io::Result<u32> {
+    match data.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| invalid_data(format!("Invalid schema_version {}", version))),
+    }
+}
+
+/// Upgrades the file contents in place to `CURRENT_SCHEMA_VERSION` and returns the version it
+/// started from. Files from a newer version are refused rather than read with missing fields.
+pub fn migrate(data: &mut Value) -> io::Result<u32> {
+    let original = schema_version(data)?;
+    if original > CURRENT_SCHEMA_VERSION {
+        // Not `InvalidData`: the file is fine, so it must not be treated as damaged
+        let message = format!(
+            "The task file uses schema version {}, but this version of TaskMaster only understands \
+             up to version {}. Please upgrade TaskMaster to open it.",
+            original, CURRENT_SCHEMA_VERSION
+        );
+        return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+    }
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(original as usize) {
+        step(data).map_err(|error| {
+            invalid_data(format!(
+                "Could not upgrade the task file from schema version {}: {}",
+                version, error
+            ))
+        })?;
+        data["schema_version"] = Value::from(version + 1);
+    }
+    Ok(original)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// Version 1 gives every task an ID and drops the edit form buffer older versions wrote to disk
+fn v0_to_v1(data: &mut Value) -> Result<(), String> {
+    let items = data
+        .get_mut("items")
+        .and_then(Value::as_array_mut)
+        .ok_or("missing list of items")?;
+    for item in items {
+        let item = item.as_object_mut().ok_or("an item is not an object")?;
+        item.remove("due_date_temp");
+        if !item.contains_key("id") {
+            item.insert("id".to_string(), Value::from(Uuid::new_v4().to_string()));
+        }
+    }
+    Ok(())
+}
@@ -1,25 +1,212 @@
+pub mod backup;
+pub mod crypto;
+pub mod emergency;
+pub mod encrypted;
 pub mod file;
+pub mod git;
+pub mod journal;
+pub mod json;
+pub mod lists;
+pub mod location;
+pub mod lock;
+pub mod memory;
+pub mod migrations;
+pub mod recovery;
+pub mod sqlite;
+pub mod task_file;
 
-pub use file::{load_from_file, save_to_file, TodoData};
+pub use encrypted::EncryptedStorage;
+pub use file::TodoData;
+pub use json::JsonFileStorage;
+pub use memory::MemoryStorage;
+pub use sqlite::SqliteStorage;
 
-use directories::BaseDirs;
-use std::path::PathBuf;
+use crate::models::TodoItem;
+use backup::Backup;
+use crypto::Key;
+use git::{GitStorage, Revision};
+use recovery::Salvage;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use uuid::Uuid;
 
-pub fn get_default_storage_path() -> PathBuf {
-    if let Some(base_dirs) = BaseDirs::new() {
-        let mut path = base_dirs.home_dir().to_path_buf();
-        path.push(".taskmaster");
-        path.push("tasks.json");
-        path
-    } else {
-        panic!("Could not determine home directory");
+/// Where the app keeps its tasks. Only `describe`, `load` and `save` are required; the rest
+/// defaults to what a backend without locking, backups or repair support can offer.
+pub trait Storage {
+    /// Where the tasks are kept, for messages, e.g. the path of the task file.
+    fn describe(&self) -> String;
+
+    /// Reads all tasks. Fails with `NotFound` if nothing has been stored yet and with `Unsupported`
+    /// if the data was written by a newer version of TaskMaster.
+    fn load(&mut self) -> io::Result<Vec<TodoItem>>;
+
+    /// Replaces all stored tasks.
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()>;
+
+    /// Adds a task or replaces the stored version of it. Like `delete`, it fails with `NotFound` if
+    /// nothing has been stored yet, as the other tasks would be missing.
+    fn upsert(&mut self, item: &TodoItem) -> io::Result<()> {
+        let mut items = self.load()?;
+        match items.iter_mut().find(|stored| stored.id == item.id) {
+            Some(stored) => *stored = item.clone(),
+            None => items.push(item.clone()),
+        }
+        self.save(&items)
+    }
+
+    /// Removes a task. Removing a task that isn't stored is not an error.
+    fn delete(&mut self, id: Uuid) -> io::Result<()> {
+        let mut items = self.load()?;
+        items.retain(|stored| stored.id != id);
+        self.save(&items)
+    }
+
+    /// Claims the stored tasks for this process. Returns `false` if another process has them.
+    fn lock(&mut self) -> io::Result<bool> {
+        Ok(true)
+    }
+
+    /// Whether someone else changed the stored tasks since they were last loaded or saved here.
+    fn changed_externally(&self) -> bool {
+        false
+    }
+
+    /// Accepts the stored tasks as they are now, so `changed_externally` stays quiet until the
+    /// next outside change.
+    fn mark_seen(&mut self) {}
+
+    /// Keeps a copy of the stored tasks as they are now.
+    fn backup(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The copies made by `backup`, newest first.
+    fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        Ok(Vec::new())
+    }
+
+    /// Backs up the current tasks and returns the ones in the given backup.
+    fn restore_backup(&mut self, _backup: &Backup) -> io::Result<Vec<TodoItem>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "This storage has no backups",
+        ))
+    }
+
+    /// Reads what can still be read from stored data that `load` can't read. The damaged data is
+    /// left where it is until `quarantine` moves it.
+    fn salvage(&self) -> io::Result<Salvage> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "This storage can't repair damaged data",
+        ))
+    }
+
+    /// Moves damaged data out of the way, so the salvaged tasks can be saved in its place, and
+    /// returns where it went.
+    fn quarantine(&mut self) -> io::Result<String> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "This storage can't repair damaged data",
+        ))
+    }
+
+    /// The names of the other task lists kept alongside this one, sorted. The main list has no
+    /// name and is not included.
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Opens a task list kept alongside this one, or the main list for `None`. A list that doesn't
+    /// exist yet is created by saving to it.
+    fn open_list(&self, _name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "This storage has only one list",
+        ))
+    }
+
+    /// The files the tasks are kept in, the main one first, e.g. for putting them under version
+    /// control.
+    fn files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Whether the tasks are encrypted, so nothing about them may be written elsewhere in plain
+    /// text.
+    fn is_encrypted(&self) -> bool {
+        false
+    }
+
+    /// The commits that changed the stored tasks, newest first.
+    fn history(&self) -> io::Result<Vec<Revision>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The tasks are not kept in a git repository",
+        ))
+    }
+
+    /// The tasks as they were stored in the given commit.
+    fn load_revision(&self, _revision: &Revision) -> io::Result<Vec<TodoItem>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The tasks are not kept in a git repository",
+        ))
+    }
+}
+
+/// Opens the storage picked with `TASKMASTER_BACKEND` for the task file at `path`: `json` (the
+/// default) for the file itself, or `sqlite` for a `tasks.db` next to it. The first time the
+/// database is used, the tasks of an existing task file are moved into it. An encrypted task file
+/// is opened with the `key` unlocked from it. If the directory of the task file is a git
+/// repository, every change is committed to it.
+pub fn open_storage(path: PathBuf, key: Option<Key>) -> io::Result<Box<dyn Storage>> {
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let backend = std::env::var("TASKMASTER_BACKEND");
+    let (storage, open): (Box<dyn Storage>, git::Opener) = match (backend.as_deref(), key) {
+        (Ok("json") | Err(_), Some(key)) => (
+            Box::new(EncryptedStorage::new(path, key.clone())),
+            Rc::new(move |path| Ok(Box::new(EncryptedStorage::new(path, key.clone())))),
+        ),
+        (Ok("json") | Err(_), None) => (
+            Box::new(JsonFileStorage::new(path)),
+            Rc::new(|path| Ok(Box::new(JsonFileStorage::new(path)))),
+        ),
+        (Ok("sqlite"), None) => {
+            let mut storage = SqliteStorage::open(path.with_file_name("tasks.db"))?;
+            storage.import_json(&path)?;
+            (
+                Box::new(storage),
+                Rc::new(|path| Ok(Box::new(SqliteStorage::open(path)?))),
+            )
+        }
+        (Ok("sqlite"), Some(_)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Encrypted task files can only be used with the JSON backend",
+            ))
+        }
+        (Ok(other), _) => {
+            let message = format!(
+                "Unknown storage backend '{}' in TASKMASTER_BACKEND, expected 'json' or 'sqlite'",
+                other
+            );
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+        }
+    };
+    if git::is_repository(&dir) {
+        return Ok(Box::new(GitStorage::open(storage, dir, open)?));
     }
+    Ok(storage)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{Status, TodoItem};
+    use crate::config::Config;
+    use crate::models::{Priority, Status, TodoItem};
+    use file::{load_from_file, save_to_file};
     use std::fs;
     use std::io::ErrorKind::NotFound;
     use tempfile::tempdir;
@@ -30,9 +217,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let file_path = temp_dir.path().join("todo_data.json");
         let todo_item = TodoItem::new(Status::Todo, "Test task", "Testing save functionality");
-        let todo_data = TodoData {
-            items: vec![todo_item.clone()],
-        };
+        let todo_data = TodoData::new(vec![todo_item.clone()]);
 
         // Act
         let result = save_to_file(&file_path, &todo_data);
@@ -54,9 +239,7 @@ mod tests {
         let temp_dir = tempdir().unwrap();
         let file_path = temp_dir.path().join("todo_data.json");
         let todo_item = TodoItem::new(Status::Todo, "Test task", "Testing load functionality");
-        let todo_data = TodoData {
-            items: vec![todo_item.clone()],
-        };
+        let todo_data = TodoData::new(vec![todo_item.clone()]);
 
         // Save the data first
         save_to_file(&file_path, &todo_data).unwrap();
@@ -84,4 +267,924 @@ mod tests {
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().kind(), NotFound);
     }
+
+    #[test]
+    fn test_load_backfills_missing_ids() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("legacy.json");
+        let legacy = r#"{"items":[
+            {"todo":"A","info":"","status":"Todo","due_date":null,"due_date_temp":null,"tags":[]},
+            {"todo":"B","info":"","status":"Todo","due_date":null,"due_date_temp":null,"tags":[]}
+        ]}"#;
+        fs::write(&file_path, legacy).unwrap();
+
+        // Act
+        let loaded_data = load_from_file(&file_path).unwrap();
+
+        // Assert
+        assert_eq!(loaded_data.items.len(), 2);
+        assert_ne!(loaded_data.items[0].id, loaded_data.items[1].id);
+        assert_eq!(loaded_data.items[0].priority, Priority::None);
+    }
+
+    #[test]
+    fn test_priority_round_trip() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("todo_data.json");
+        let mut todo_item = TodoItem::new(Status::Todo, "Test task", "Testing priority");
+        todo_item.priority = Priority::Urgent;
+        let todo_data = TodoData::new(vec![todo_item]);
+
+        // Act
+        save_to_file(&file_path, &todo_data).unwrap();
+        let loaded_data = load_from_file(&file_path).unwrap();
+
+        // Assert
+        assert_eq!(loaded_data.items[0].priority, Priority::Urgent);
+    }
+
+    #[test]
+    fn test_ids_survive_round_trip() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("todo_data.json");
+        let todo_item = TodoItem::new(Status::Todo, "Test task", "Testing IDs");
+        let todo_data = TodoData::new(vec![todo_item.clone(), todo_item.clone()]);
+
+        // Act
+        save_to_file(&file_path, &todo_data).unwrap();
+        let loaded_data = load_from_file(&file_path).unwrap();
+
+        // Assert: the first keeps its ID, the duplicate gets a new one
+        assert_eq!(loaded_data.items[0].id, todo_item.id);
+        assert_ne!(loaded_data.items[1].id, todo_item.id);
+    }
+
+    #[test]
+    fn test_load_migrates_unversioned_file() {
+        // Arrange: a file as written before schema versions, with the form buffer on disk
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let legacy = r#"{"items":[{"todo":"Old task","info":"","status":"Todo","due_date":null,"due_date_temp":"2024-01-01","tags":[]}]}"#;
+        fs::write(&file_path, legacy).unwrap();
+
+        // Act
+        let loaded_data = load_from_file(&file_path).unwrap();
+        save_to_file(&file_path, &loaded_data).unwrap();
+
+        // Assert
+        assert_eq!(
+            loaded_data.schema_version,
+            migrations::CURRENT_SCHEMA_VERSION
+        );
+        assert_eq!(loaded_data.items[0].todo, "Old task");
+        assert!(loaded_data.items[0].due_date_temp.is_none());
+        let backup = file::migration_backup_path(&file_path, 0);
+        assert_eq!(fs::read_to_string(backup).unwrap(), legacy);
+        let saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&file_path).unwrap()).unwrap();
+        assert_eq!(
+            migrations::schema_version(&saved).unwrap(),
+            migrations::CURRENT_SCHEMA_VERSION
+        );
+    }
+
+    #[test]
+    fn test_load_refuses_newer_schema() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let newer = format!(
+            r#"{{"schema_version":{},"items":[]}}"#,
+            migrations::CURRENT_SCHEMA_VERSION + 1
+        );
+        fs::write(&file_path, &newer).unwrap();
+
+        // Act
+        let result = load_from_file(&file_path);
+
+        // Assert: the error explains itself and the file is left alone
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+        assert!(error.to_string().contains("upgrade TaskMaster"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), newer);
+    }
+
+    #[test]
+    fn test_save_replaces_file_without_leftovers() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        fs::write(&file_path, "previous contents").unwrap();
+        let todo_data = TodoData::new(vec![TodoItem::new(Status::Todo, "Test task", "")]);
+
+        // Act
+        save_to_file(&file_path, &todo_data).unwrap();
+
+        // Assert: only the task file is left in the directory
+        let entries: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            load_from_file(&file_path).unwrap().items[0].todo,
+            "Test task"
+        );
+    }
+
+    #[test]
+    fn test_backups_rotate() {
+        // Arrange: two older backups and an unrelated file
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        fs::write(&file_path, "{}").unwrap();
+        let dir = backup::backup_dir(&file_path);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("tasks-20200101-090000.json"), "{}").unwrap();
+        fs::write(dir.join("tasks-20200102-090000.json"), "{}").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        // Act
+        let created = backup::create_backup(&file_path, 2).unwrap().unwrap();
+
+        // Assert: the newest two are kept, newest first
+        let backups = backup::list_backups(&file_path).unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[0].path, created);
+        assert!(backups[1].path.ends_with("tasks-20200102-090000.json"));
+        assert!(!dir.join("tasks-20200101-090000.json").exists());
+        assert!(dir.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_no_backup_when_disabled_or_missing() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+
+        // Act & Assert
+        assert_eq!(backup::create_backup(&file_path, 5).unwrap(), None);
+        fs::write(&file_path, "{}").unwrap();
+        assert_eq!(backup::create_backup(&file_path, 0).unwrap(), None);
+        assert!(backup::list_backups(&file_path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_error_points_at_location() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        fs::write(
+            &file_path,
+            "{\n  \"schema_version\": 1,\n  \"items\": [\n    {\"todo\": }\n",
+        )
+        .unwrap();
+
+        // Act
+        let error = load_from_file(&file_path).unwrap_err();
+
+        // Assert
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("line 4"), "{}", error);
+    }
+
+    #[test]
+    fn test_salvage_truncated_file() {
+        // Arrange: a file cut off in the middle of its third item
+        let todo_data = TodoData::new(vec![
+            TodoItem::new(Status::Todo, "First", ""),
+            TodoItem::new(Status::Completed, "Second", ""),
+            TodoItem::new(Status::Todo, "Third", ""),
+        ]);
+        let json = serde_json::to_string_pretty(&todo_data).unwrap();
+        let cut = json.find("Third").unwrap();
+
+        // Act
+        let salvage = recovery::salvage(&json[..cut]);
+
+        // Assert
+        let names: Vec<_> = salvage
+            .items
+            .iter()
+            .map(|item| item.todo.as_str())
+            .collect();
+        assert_eq!(names, ["First", "Second"]);
+        assert_eq!(salvage.items[0].id, todo_data.items[0].id);
+    }
+
+    #[test]
+    fn test_salvage_skips_invalid_items() {
+        // Arrange: valid JSON where one item has an unknown status
+        let contents = r#"{"schema_version":1,"items":[
+            {"todo":"Good","info":"","status":"Todo","due_date":null,"tags":[]},
+            {"todo":"Bad","info":"","status":"Someday","due_date":null,"tags":[]}
+        ]}"#;
+
+        // Act
+        let salvage = recovery::salvage(contents);
+
+        // Assert
+        assert_eq!(salvage.items.len(), 1);
+        assert_eq!(salvage.items[0].todo, "Good");
+        assert_eq!(salvage.skipped, 1);
+    }
+
+    #[test]
+    fn test_quarantine_moves_file_aside() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        fs::write(&file_path, "{ broken").unwrap();
+
+        // Act
+        let moved_to = recovery::quarantine(&file_path).unwrap();
+
+        // Assert
+        assert!(!file_path.exists());
+        assert_eq!(fs::read_to_string(&moved_to).unwrap(), "{ broken");
+        assert!(moved_to
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("tasks.json.corrupt-"));
+    }
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+
+        // Act
+        let first = lock::try_lock(&file_path).unwrap();
+        let second = lock::try_lock(&file_path).unwrap();
+
+        // Assert: the second holder is turned away until the first lets go
+        assert!(first.is_some());
+        assert!(second.is_none());
+        drop(first);
+        assert!(lock::try_lock(&file_path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_modified_time_tracks_saves() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        assert_eq!(file::modified_time(&file_path), None);
+
+        // Act
+        save_to_file(&file_path, &TodoData::new(Vec::new())).unwrap();
+
+        // Assert
+        assert!(file::modified_time(&file_path).is_some());
+    }
+
+    #[test]
+    fn test_json_storage_upsert_and_delete() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let mut storage = JsonFileStorage::new(temp_dir.path().join("tasks.json"));
+        let mut first = TodoItem::new(Status::Todo, "First", "");
+        let second = TodoItem::new(Status::Todo, "Second", "");
+        storage.save(&[first.clone()]).unwrap();
+
+        // Act
+        first.todo = "First, renamed".to_string();
+        storage.upsert(&first).unwrap();
+        storage.upsert(&second).unwrap();
+        storage.delete(first.id).unwrap();
+
+        // Assert
+        let items = storage.load().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, second.id);
+        assert!(!storage.changed_externally());
+    }
+
+    #[test]
+    fn test_json_storage_notices_outside_changes() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        storage.save(&[]).unwrap();
+
+        // Act: another process writes the file a little later
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let todo_data = TodoData::new(vec![TodoItem::new(Status::Todo, "Theirs", "")]);
+        save_to_file(&file_path, &todo_data).unwrap();
+
+        // Assert
+        assert!(storage.changed_externally());
+        storage.mark_seen();
+        assert!(!storage.changed_externally());
+    }
+
+    #[test]
+    fn test_memory_storage_starts_empty() {
+        // Arrange
+        let mut storage = MemoryStorage::default();
+        let item = TodoItem::new(Status::Todo, "Task", "");
+
+        // Act & Assert: nothing to update until the list is saved once
+        assert_eq!(storage.load().unwrap_err().kind(), NotFound);
+        assert_eq!(storage.upsert(&item).unwrap_err().kind(), NotFound);
+        storage.save(&[]).unwrap();
+        storage.upsert(&item).unwrap();
+        assert_eq!(storage.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_storage_round_trip() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("tasks.db")).unwrap();
+        let first = TodoItem::new(Status::Todo, "First", "Some info");
+        let others: Vec<_> = (0..5)
+            .map(|i| TodoItem::new(Status::Todo, &format!("Other {}", i), ""))
+            .collect();
+        let mut second = TodoItem::new(Status::InProgress, "Second", "");
+        second.priority = Priority::High;
+        // Tags and dependencies come back as written, repeats and order included
+        second.tags = ["work", "urgent", "work", "a"].map(String::from).to_vec();
+        second.depends_on = others.iter().rev().map(|other| other.id).collect();
+        second.depends_on.insert(2, first.id);
+        second.parent_id = Some(first.id);
+        second.estimate_minutes = Some(90);
+        second.set_due(crate::models::Due::at(
+            chrono::NaiveDate::from_ymd_opt(2026, 11, 2)
+                .unwrap()
+                .and_hms_opt(9, 30, 0)
+                .unwrap(),
+            chrono_tz::Europe::Copenhagen,
+        ));
+        second.time_log = vec![crate::models::TimeEntry {
+            start: chrono::Utc::now() - chrono::Duration::hours(1),
+            end: Some(chrono::Utc::now()),
+        }];
+
+        // Act
+        assert_eq!(storage.load().unwrap_err().kind(), NotFound);
+        let mut items = vec![first, second];
+        items.extend(others);
+        storage.save(&items).unwrap();
+        let loaded = storage.load().unwrap();
+
+        // Assert
+        let as_json = |items: &[TodoItem]| serde_json::to_value(items).unwrap();
+        assert_eq!(as_json(&loaded), as_json(&items));
+    }
+
+    #[test]
+    fn test_sqlite_storage_upgrades_older_databases() {
+        // Arrange: a database of the first layout, without time zones and with tags and
+        // dependencies keyed by their value
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("tasks.db");
+        let later = TodoItem::new(Status::Todo, "Later", "");
+        let sooner = TodoItem::new(Status::Todo, "Sooner", "");
+        let mut task = TodoItem::new(Status::Todo, "Old", "");
+        task.tags = ["work", "home"].map(String::from).to_vec();
+        task.depends_on = vec![later.id, sooner.id];
+        let items = [task, later, sooner];
+        SqliteStorage::open(path.clone())
+            .unwrap()
+            .save(&items)
+            .unwrap();
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                "ALTER TABLE tasks DROP COLUMN due_zone;
+                CREATE TABLE old_tags (task_id TEXT NOT NULL, position INTEGER NOT NULL,
+                    tag TEXT NOT NULL, PRIMARY KEY (task_id, tag));
+                INSERT INTO old_tags SELECT task_id, position, tag FROM tags;
+                DROP TABLE tags;
+                ALTER TABLE old_tags RENAME TO tags;
+                CREATE TABLE old_dependencies (task_id TEXT NOT NULL, depends_on TEXT NOT NULL,
+                    PRIMARY KEY (task_id, depends_on));
+                INSERT INTO old_dependencies
+                    SELECT task_id, depends_on FROM dependencies ORDER BY position;
+                DROP TABLE dependencies;
+                ALTER TABLE old_dependencies RENAME TO dependencies;
+                PRAGMA user_version = 1;",
+            )
+            .unwrap();
+
+        // Act
+        let mut storage = SqliteStorage::open(path).unwrap();
+
+        // Assert
+        assert_eq!(storage.load().unwrap(), items);
+    }
+
+    #[test]
+    fn test_sqlite_storage_upsert_and_delete() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("tasks.db")).unwrap();
+        let mut first = TodoItem::new(Status::Todo, "First", "");
+        let second = TodoItem::new(Status::Todo, "Second", "");
+        let third = TodoItem::new(Status::Todo, "Third", "");
+        assert_eq!(storage.upsert(&first).unwrap_err().kind(), NotFound);
+        storage.save(&[first.clone(), second.clone()]).unwrap();
+
+        // Act
+        first.tags = vec!["renamed".to_string()];
+        first.todo = "First, renamed".to_string();
+        storage.upsert(&first).unwrap();
+        storage.upsert(&third).unwrap();
+        storage.delete(second.id).unwrap();
+
+        // Assert: the changed task keeps its place and the new one goes last
+        let items = storage.load().unwrap();
+        let titles: Vec<&str> = items.iter().map(|item| item.todo.as_str()).collect();
+        assert_eq!(titles, ["First, renamed", "Third"]);
+        assert_eq!(items[0].tags, ["renamed"]);
+    }
+
+    #[test]
+    fn test_sqlite_storage_imports_json_once() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let json_path = temp_dir.path().join("tasks.json");
+        let todo_data = TodoData::new(vec![TodoItem::new(Status::Todo, "From JSON", "")]);
+        save_to_file(&json_path, &todo_data).unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("tasks.db")).unwrap();
+
+        // Act
+        let imported = storage.import_json(&json_path).unwrap();
+
+        // Assert
+        assert!(imported);
+        assert!(!json_path.exists());
+        assert!(temp_dir.path().join("tasks.json.imported").exists());
+        assert_eq!(storage.load().unwrap()[0].todo, "From JSON");
+        assert!(!storage.import_json(&json_path).unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_storage_notices_outside_changes() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("tasks.db");
+        let mut storage = SqliteStorage::open(db_path.clone()).unwrap();
+        storage.save(&[]).unwrap();
+        storage.load().unwrap();
+        let mut other = SqliteStorage::open(db_path).unwrap();
+
+        // Act: another process adds a task
+        assert!(!storage.changed_externally());
+        other
+            .upsert(&TodoItem::new(Status::Todo, "Theirs", ""))
+            .unwrap();
+
+        // Assert
+        assert!(storage.changed_externally());
+        storage.mark_seen();
+        assert!(!storage.changed_externally());
+        assert_eq!(storage.load().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sqlite_storage_backup_and_restore() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let mut storage = SqliteStorage::open(temp_dir.path().join("tasks.db")).unwrap();
+        storage
+            .save(&[TodoItem::new(Status::Todo, "Before", "")])
+            .unwrap();
+
+        // Act
+        storage.backup().unwrap();
+        storage.save(&[]).unwrap();
+        let backups = storage.list_backups().unwrap();
+        let restored = storage.restore_backup(&backups[0]).unwrap();
+
+        // Assert
+        assert_eq!(backups.len(), 1);
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].todo, "Before");
+    }
+
+    #[test]
+    fn test_move_legacy_data() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join(".taskmaster");
+        let data_dir = temp_dir.path().join("share").join("taskmaster");
+        let todo_data = TodoData::new(vec![TodoItem::new(Status::Todo, "Old task", "")]);
+        save_to_file(&legacy.join("tasks.json"), &todo_data).unwrap();
+        backup::create_backup(&legacy.join("tasks.json"), 1).unwrap();
+
+        // Act
+        let moved = location::move_legacy_data(&legacy, &data_dir).unwrap();
+
+        // Assert
+        assert!(moved);
+        assert!(!legacy.exists());
+        let loaded = load_from_file(&data_dir.join("tasks.json")).unwrap();
+        assert_eq!(loaded.items[0].todo, "Old task");
+        assert_eq!(
+            backup::list_backups(&data_dir.join("tasks.json"))
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(!location::move_legacy_data(&legacy, &data_dir).unwrap());
+    }
+
+    #[test]
+    fn test_move_legacy_data_keeps_existing_tasks() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let legacy = temp_dir.path().join("legacy");
+        let data_dir = temp_dir.path().join("data");
+        save_to_file(&legacy.join("tasks.json"), &TodoData::new(vec![])).unwrap();
+        save_to_file(&data_dir.join("tasks.json"), &TodoData::new(vec![])).unwrap();
+
+        // Act & Assert
+        assert!(!location::move_legacy_data(&legacy, &data_dir).unwrap());
+        assert!(legacy.join("tasks.json").exists());
+    }
+
+    #[test]
+    fn test_file_flag_overrides_config() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+        fs::write(&config_path, r#"{ "file": "lists/work.json" }"#).unwrap();
+        let config = Config::load_from(&config_path).unwrap();
+
+        // Act
+        let (path, moved_from) =
+            location::resolve(Some(PathBuf::from("other.json")), None, &config).unwrap();
+
+        // Assert
+        assert_eq!(config.file, Some(temp_dir.path().join("lists/work.json")));
+        assert_eq!(path, PathBuf::from("other.json"));
+        assert_eq!(moved_from, None);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let config_path = temp_dir.path().join("config.json");
+
+        // Act & Assert: a missing file means defaults, a broken one is reported
+        assert!(Config::load_from(&config_path).unwrap().file.is_none());
+        fs::write(&config_path, r#"{ "fiel": "tasks.json" }"#).unwrap();
+        let error = Config::load_from(&config_path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("config.json"));
+    }
+
+    #[test]
+    fn test_lists_are_kept_next_to_the_main_list() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let main = temp_dir.path().join("tasks.json");
+        let storage = JsonFileStorage::new(main.clone());
+
+        // Act
+        let mut work = storage.open_list(Some("Work")).unwrap();
+        work.save(&[TodoItem::new(Status::Todo, "Report", "")])
+            .unwrap();
+        let mut private = work.open_list(Some("private errands")).unwrap();
+        private.save(&[]).unwrap();
+
+        // Assert
+        assert!(temp_dir.path().join("lists").join("Work.json").exists());
+        assert_eq!(storage.list_names().unwrap(), ["private errands", "Work"]);
+        assert_eq!(work.list_names().unwrap(), storage.list_names().unwrap());
+        assert_eq!(
+            storage.open_list(None).unwrap().describe(),
+            main.display().to_string()
+        );
+        assert_eq!(
+            lists::list_path(&main, Some("Work")),
+            temp_dir.path().join("lists/Work.json")
+        );
+    }
+
+    #[test]
+    fn test_list_names() {
+        assert!(lists::is_valid_name("Work"));
+        assert!(lists::is_valid_name("Side project_2"));
+        assert!(!lists::is_valid_name(""));
+        assert!(!lists::is_valid_name(" Work"));
+        assert!(!lists::is_valid_name("../tasks"));
+        assert!(!lists::is_valid_name("inbox"));
+    }
+
+    #[test]
+    fn test_project_file_is_found_above_the_current_directory() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let project = temp_dir.path().join("repo");
+        let nested = project.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        let path = location::init_project(&project).unwrap();
+        save_to_file(&path, &TodoData::new(vec![])).unwrap();
+
+        // Act
+        let found = location::find_project_file(&nested, None);
+
+        // Assert
+        assert_eq!(found, Some(project.join(".taskmaster").join("tasks.json")));
+        assert!(project.join(".taskmaster").join(".gitignore").exists());
+        assert_eq!(location::find_project_file(temp_dir.path(), None), None);
+        // The home directory's `.taskmaster` holds personal tasks, not a project's
+        assert_eq!(location::find_project_file(&nested, Some(&project)), None);
+    }
+
+    #[test]
+    fn test_init_project_refuses_existing_tasks() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let path = location::init_project(temp_dir.path()).unwrap();
+        save_to_file(&path, &TodoData::new(vec![])).unwrap();
+
+        // Act
+        let error = location::init_project(temp_dir.path()).unwrap_err();
+
+        // Assert
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_project_file_comes_before_config() {
+        // Arrange
+        let config = Config {
+            file: Some(PathBuf::from("personal.json")),
+        };
+        let project = PathBuf::from("repo/.taskmaster/tasks.json");
+
+        // Act
+        let (path, _) = location::resolve(None, Some(project.clone()), &config).unwrap();
+
+        // Assert
+        assert_eq!(path, project);
+    }
+
+    #[test]
+    fn test_journal_is_replayed_and_compacted() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        let mut first = TodoItem::new(Status::Todo, "First", "");
+        let second = TodoItem::new(Status::Todo, "Second", "");
+        storage.save(&[first.clone(), second.clone()]).unwrap();
+
+        // Act: single changes only go to the journal
+        first.status = Status::Completed;
+        storage.upsert(&first).unwrap();
+        storage.delete(second.id).unwrap();
+
+        // Assert
+        assert_eq!(load_from_file(&file_path).unwrap().items.len(), 2);
+        assert_eq!(journal::read(&file_path).unwrap().len(), 2);
+        let items = JsonFileStorage::new(file_path.clone()).load().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].status, Status::Completed);
+
+        // A full save folds the journal into the file
+        storage.save(&items).unwrap();
+        assert!(!journal::journal_path(&file_path).exists());
+        assert_eq!(load_from_file(&file_path).unwrap().items.len(), 1);
+    }
+
+    #[test]
+    fn test_journal_survives_a_cut_off_entry() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        storage.save(&[]).unwrap();
+        storage
+            .upsert(&TodoItem::new(Status::Todo, "Before the crash", ""))
+            .unwrap();
+        let journal_path = journal::journal_path(&file_path);
+        let mut contents = fs::read_to_string(&journal_path).unwrap();
+        contents.push_str(r#"{"at":"2026-10-18T10:00:00Z","v":1,"upsert":{"id""#);
+        fs::write(&journal_path, contents).unwrap();
+
+        // Act
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        storage.load().unwrap();
+        storage
+            .upsert(&TodoItem::new(Status::Todo, "After the crash", ""))
+            .unwrap();
+
+        // Assert
+        let titles: Vec<String> = storage
+            .load()
+            .unwrap()
+            .into_iter()
+            .map(|item| item.todo)
+            .collect();
+        assert_eq!(titles, ["Before the crash", "After the crash"]);
+    }
+
+    #[test]
+    fn test_journal_is_compacted_when_long() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let mut storage = JsonFileStorage::new(file_path.clone());
+        storage.save(&[]).unwrap();
+        let mut item = TodoItem::new(Status::Todo, "Busy task", "");
+
+        // Act
+        for i in 0..journal::COMPACT_AFTER {
+            item.info = format!("Change {}", i);
+            storage.upsert(&item).unwrap();
+        }
+
+        // Assert
+        assert!(!journal::journal_path(&file_path).exists());
+        let items = load_from_file(&file_path).unwrap().items;
+        assert_eq!(
+            items[0].info,
+            format!("Change {}", journal::COMPACT_AFTER - 1)
+        );
+    }
+
+    #[test]
+    fn test_emergency_dump_round_trip() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let dump = emergency::Dump {
+            list: Some("Work".to_string()),
+            written_at: chrono::Utc::now(),
+            items: vec![TodoItem::new(Status::Todo, "Unsaved", "")],
+        };
+
+        // Act
+        let written = emergency::write(&file_path, &dump, None).unwrap();
+        let read = emergency::read(&file_path, None).unwrap().unwrap();
+
+        // Assert
+        assert_eq!(written, temp_dir.path().join("tasks.json.recovery"));
+        assert_eq!(read.list, dump.list);
+        assert_eq!(read.items, dump.items);
+        emergency::discard(&file_path).unwrap();
+        assert!(emergency::read(&file_path, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_git_storage_commits_each_change() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(temp_dir.path())
+            .status()
+            .unwrap();
+        let mut storage = GitStorage::open(
+            Box::new(JsonFileStorage::new(file_path.clone())),
+            temp_dir.path().to_path_buf(),
+            Rc::new(|path| Ok(Box::new(JsonFileStorage::new(path)))),
+        )
+        .unwrap();
+        let mut approval = TodoItem::new(Status::Todo, "Security approval", "");
+        let budget = TodoItem::new(Status::Todo, "Budget", "");
+        // Something of the user's own is staged already
+        fs::write(temp_dir.path().join("notes.txt"), "draft").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "notes.txt"])
+            .current_dir(temp_dir.path())
+            .status()
+            .unwrap();
+
+        // Act
+        storage.save(&[approval.clone(), budget.clone()]).unwrap();
+        approval.status = Status::Completed;
+        storage.upsert(&approval).unwrap();
+        storage.delete(budget.id).unwrap();
+
+        // Assert
+        let history = storage.history().unwrap();
+        let messages: Vec<&str> = history.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "delete: Budget",
+                "complete: Security approval",
+                "update 2 tasks"
+            ]
+        );
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(args)
+                .current_dir(temp_dir.path())
+                .output()
+                .unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+        assert_eq!(
+            git(&["log", "--format=%s", "--", ".gitignore"]).trim(),
+            "ignore TaskMaster's locks, backups and recovery files"
+        );
+        // The user's staged file is in none of the commits and is still staged
+        assert_eq!(git(&["log", "--format=%s", "--", "notes.txt"]), "");
+        assert_eq!(
+            git(&["diff", "--cached", "--name-only"]).trim(),
+            "notes.txt"
+        );
+        let first = storage.load_revision(&history[2]).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].status, Status::Todo);
+        let second = storage.load_revision(&history[1]).unwrap();
+        assert_eq!(second[0].status, Status::Completed);
+    }
+
+    #[test]
+    fn test_encrypted_storage_round_trip() {
+        // Arrange
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let key = Key::generate("correct horse").unwrap();
+        let mut storage = EncryptedStorage::new(file_path.clone(), key);
+        let task = TodoItem::new(Status::Todo, "Calculate private financing for company", "");
+
+        // Act
+        storage.save(std::slice::from_ref(&task)).unwrap();
+
+        // Assert
+        let contents = fs::read(&file_path).unwrap();
+        assert!(!String::from_utf8_lossy(&contents).contains("financing"));
+        assert!(crypto::is_encrypted(&file_path));
+        assert!(crypto::unlock(&file_path, "wrong horse").is_err());
+        let key = crypto::unlock(&file_path, "correct horse").unwrap();
+        assert_eq!(
+            EncryptedStorage::new(file_path, key).load().unwrap(),
+            [task]
+        );
+    }
+
+    #[test]
+    fn test_encrypting_and_decrypting_a_store() {
+        // Arrange: a main list with a pending journal entry and a backup, and a second list
+        let temp_dir = tempdir().unwrap();
+        let file_path = temp_dir.path().join("tasks.json");
+        let mut main = JsonFileStorage::new(file_path.clone());
+        let mut task = TodoItem::new(Status::Todo, "Client meeting", "");
+        main.save(&[task.clone()]).unwrap();
+        main.backup().unwrap();
+        task.status = Status::Completed;
+        main.upsert(&task).unwrap();
+        let work = TodoItem::new(Status::Todo, "Invoice", "");
+        main.open_list(Some("Work"))
+            .unwrap()
+            .save(std::slice::from_ref(&work))
+            .unwrap();
+        // Plain copies left by a migration, a damaged file and the SQLite import
+        let copies = [
+            "tasks.json.v0.bak",
+            "tasks.json.corrupt-x",
+            "tasks.json.imported",
+        ]
+        .map(|name| temp_dir.path().join(name));
+        for copy in &copies {
+            fs::write(copy, "Client meeting").unwrap();
+        }
+        main.lock().unwrap();
+        let key = Key::generate("secret").unwrap();
+
+        // Act
+        let encrypted_copies = encrypted::encrypt_store(&file_path, &key).unwrap();
+
+        // Assert
+        let backup = main.list_backups().unwrap().remove(0).path;
+        let work_path = lists::list_path(&file_path, Some("Work"));
+        for path in [&file_path, &backup, &work_path].into_iter().chain(&copies) {
+            assert!(crypto::is_encrypted(path));
+        }
+        assert_eq!(encrypted_copies.len(), copies.len());
+        assert!(!crypto::is_encrypted(&lock::lock_path(&file_path)));
+        assert!(!journal::journal_path(&file_path).exists());
+        let mut storage = EncryptedStorage::new(file_path.clone(), key.clone());
+        assert_eq!(storage.load().unwrap(), [task.clone()]);
+        assert_eq!(
+            storage.open_list(Some("Work")).unwrap().load().unwrap(),
+            [work]
+        );
+
+        // And back
+        encrypted::decrypt_store(&file_path, &key).unwrap();
+        assert_eq!(load_from_file(&file_path).unwrap().items, [task]);
+        assert_eq!(
+            load_from_file(&backup).unwrap().items[0].status,
+            Status::Todo
+        );
+        assert!(!crypto::is_encrypted(&work_path));
+        for copy in &copies {
+            assert_eq!(fs::read_to_string(copy).unwrap(), "Client meeting");
+        }
+    }
 }
@@ -0,0 +1,97 @@
+// The recovery.rs file deals with task files that can no longer be read as a whole, e.g. after a
+// partial write or a bad hand edit. Every item that still parses on its own is salvaged, and the
+// damaged file is moved aside before the salvaged items are saved, so it is never overwritten.
+
+use crate::models::TodoItem;
+use crate::storage::migrations;
+use chrono::Local;
+use serde_json::{json, Deserializer, Value};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The items that could be read from a damaged file.
+#[derive(Debug, Default)]
+pub struct Salvage {
+    pub items: Vec<TodoItem>,
+    /// Items that were found but could not be read.
+    pub skipped: usize,
+}
+
+/// Moves a damaged file aside to e.g. `tasks.json.corrupt-20261018-143000` and returns the new
+/// path.
+pub fn quarantine(path: &Path) -> io::Result<PathBuf> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", Local::now().format("%Y%m%d-%H%M%S")));
+    let target = path.with_file_name(name);
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Reads whatever items can be read from the contents of a damaged file. If the JSON is cut off,
+/// the items before the damage are kept; items that are complete but invalid are skipped.
+pub fn salvage(contents: &str) -> Salvage {
+    let (version, values) = match serde_json::from_str::<Value>(contents) {
+        Ok(mut data) => {
+            let version = migrations::schema_version(&data).ok();
+            match data.get_mut("items").map(Value::take) {
+                Some(Value::Array(items)) => (version, items),
+                _ => (version, Vec::new()),
+            }
+        }
+        Err(_) => leading_items(contents),
+    };
+
+    let mut salvage = Salvage::default();
+    for value in values {
+        // Run each item through the migrations on its own, so one bad item can't sink the rest
+        let mut data = json!({ "schema_version": version.unwrap_or(0), "items": [value] });
+        let item = migrations::migrate(&mut data)
+            .ok()
+            .and_then(|_| serde_json::from_value::<TodoItem>(data["items"][0].take()).ok());
+        match item {
+            Some(item) => salvage.items.push(item),
+            None => salvage.skipped += 1,
+        }
+    }
+    salvage
+}
+
+// Reads the schema version and the complete items at the start of a file that is not valid JSON,
+// stopping at the first item that can't be parsed
+fn leading_items(contents: &str) -> (Option<u32>, Vec<Value>) {
+    let Some(items_at) = contents.find("\"items\"") else {
+        return (None, Vec::new());
+    };
+    let version = contents[..items_at]
+        .split_once("\"schema_version\"")
+        .and_then(|(_, rest)| {
+            let digits: String = rest
+                .trim_start_matches([' ', '\n', '\r', '\t', ':'])
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            digits.parse().ok()
+        });
+    let Some(list_at) = contents[items_at..].find('[') else {
+        return (version, Vec::new());
+    };
+
+    let mut rest = &contents[items_at + list_at + 1..];
+    let mut values = Vec::new();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() || rest.starts_with(']') {
+            break;
+        }
+        let mut stream = Deserializer::from_str(rest).into_iter::<Value>();
+        match stream.next() {
+            Some(Ok(value)) => {
+                values.push(value);
+                rest = &rest[stream.byte_offset()..];
+            }
+            _ => break,
+        }
+    }
+    (version, values)
+}
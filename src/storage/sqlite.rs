@@ -0,0 +1,535 @@
+// The sqlite.rs file keeps the tasks in an SQLite database, by default `tasks.db` next to the task file.
+// Every task is a row, with its tags, dependencies and logged time in tables of their own, so a
+// change to one task only rewrites that task's rows instead of the whole list.
+
+use crate::models::{Recurrence, TimeEntry, TodoItem};
+use crate::storage::backup::Backup;
+use crate::storage::file::load_from_file;
+use crate::storage::task_file::TaskFile;
+use crate::storage::Storage;
+use chrono_tz::Tz;
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The database layout written by this build, kept in `PRAGMA user_version`.
+const SCHEMA_VERSION: i64 = 3;
+
+/// Brings a database of an older layout up to date: `UPGRADES[n]` takes version `n + 1` to
+/// `n + 2`.
+const UPGRADES: [&str; SCHEMA_VERSION as usize - 1] = [
+    // The time zone of timed tasks
+    "ALTER TABLE tasks ADD COLUMN due_zone TEXT;",
+    // Tags and dependencies keyed by their position, so repeated ones are kept and both come back
+    // in order
+    "CREATE TABLE new_tags (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );
+    INSERT INTO new_tags SELECT task_id, position, tag FROM tags;
+    DROP TABLE tags;
+    ALTER TABLE new_tags RENAME TO tags;
+    CREATE INDEX tags_by_tag ON tags (tag);
+    CREATE TABLE new_dependencies (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        depends_on TEXT NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );
+    INSERT INTO new_dependencies
+        SELECT task_id, row_number() OVER (PARTITION BY task_id ORDER BY rowid) - 1, depends_on
+        FROM dependencies;
+    DROP TABLE dependencies;
+    ALTER TABLE new_dependencies RENAME TO dependencies;",
+];
+
+const SCHEMA: &str = "
+    CREATE TABLE tasks (
+        id TEXT PRIMARY KEY,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        info TEXT NOT NULL,
+        status TEXT NOT NULL,
+        priority TEXT NOT NULL,
+        due_date TEXT,
+        due_at TEXT,
+        due_zone TEXT,
+        scheduled TEXT,
+        parent_id TEXT,
+        collapsed INTEGER NOT NULL,
+        recurrence TEXT,
+        estimate_minutes INTEGER,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        completed_at TEXT
+    );
+    CREATE TABLE tags (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );
+    CREATE INDEX tags_by_tag ON tags (tag);
+    CREATE TABLE dependencies (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        depends_on TEXT NOT NULL,
+        PRIMARY KEY (task_id, position)
+    );
+    CREATE TABLE time_entries (
+        task_id TEXT NOT NULL REFERENCES tasks (id) ON DELETE CASCADE,
+        start TEXT NOT NULL,
+        end TEXT
+    );
+    CREATE INDEX time_entries_by_task ON time_entries (task_id);
+";
+
+pub struct SqliteStorage {
+    file: TaskFile,
+    connection: Connection,
+    // Each task as last read or written here, with its position, so saves can skip unchanged rows
+    written: HashMap<Uuid, (usize, String)>,
+    // `PRAGMA data_version` when the tasks were last read; it changes when another connection
+    // writes
+    seen_version: Option<i64>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: PathBuf) -> io::Result<Self> {
+        Self::open_file(TaskFile::new(path))
+    }
+
+    fn open_file(file: TaskFile) -> io::Result<Self> {
+        if let Some(parent) = file.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut connection = Connection::open(&file.path).map_err(to_io)?;
+        connection
+            .execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+            .map_err(to_io)?;
+        upgrade_schema(&mut connection)?;
+        Ok(Self {
+            file,
+            connection,
+            written: HashMap::new(),
+            seen_version: None,
+        })
+    }
+
+    /// Moves the tasks of a JSON task file into an empty database, once. The file is renamed to
+    /// e.g. `tasks.json.imported` afterwards so it isn't picked up again. Returns whether anything
+    /// was imported.
+    pub fn import_json(&mut self, json_path: &Path) -> io::Result<bool> {
+        if has_schema(&self.connection)? || !json_path.exists() {
+            return Ok(false);
+        }
+        let todo_data = load_from_file(json_path)?;
+        self.save(&todo_data.items)?;
+        let mut imported = json_path.as_os_str().to_os_string();
+        imported.push(".imported");
+        fs::rename(json_path, imported)?;
+        Ok(true)
+    }
+
+    fn data_version(&self) -> Option<i64> {
+        self.connection
+            .query_row("PRAGMA data_version", [], |row| row.get(0))
+            .ok()
+    }
+
+    // Remembers a task as written, so the next save can tell whether it changed
+    fn remember(&mut self, item: &TodoItem, position: usize) -> io::Result<()> {
+        let json = serde_json::to_string(item)?;
+        self.written.insert(item.id, (position, json));
+        Ok(())
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn describe(&self) -> String {
+        self.file.path.display().to_string()
+    }
+
+    fn load(&mut self) -> io::Result<Vec<TodoItem>> {
+        let items = read_items(&self.connection)?;
+        self.written.clear();
+        for (position, item) in items.iter().enumerate() {
+            self.remember(item, position)?;
+        }
+        self.seen_version = self.data_version();
+        Ok(items)
+    }
+
+    fn save(&mut self, items: &[TodoItem]) -> io::Result<()> {
+        let transaction = self.connection.transaction().map_err(to_io)?;
+        if !has_schema(&transaction)? {
+            create_schema(&transaction)?;
+        }
+
+        // Drop the tasks that are gone, then write the ones that changed or moved
+        let keep: HashSet<String> = items.iter().map(|item| item.id.to_string()).collect();
+        let stored: Vec<String> = transaction
+            .prepare("SELECT id FROM tasks")
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| row.get(0))?
+                    .collect::<Result<_, _>>()
+            })
+            .map_err(to_io)?;
+        for id in stored.iter().filter(|id| !keep.contains(*id)) {
+            transaction
+                .execute("DELETE FROM tasks WHERE id = ?1", [id])
+                .map_err(to_io)?;
+        }
+        let mut written = HashMap::with_capacity(items.len());
+        for (position, item) in items.iter().enumerate() {
+            let entry = (position, serde_json::to_string(item)?);
+            if self.written.get(&item.id) != Some(&entry) {
+                write_item(&transaction, item, position)?;
+            }
+            written.insert(item.id, entry);
+        }
+        transaction.commit().map_err(to_io)?;
+        self.written = written;
+        Ok(())
+    }
+
+    fn upsert(&mut self, item: &TodoItem) -> io::Result<()> {
+        let transaction = self.connection.transaction().map_err(to_io)?;
+        if !has_schema(&transaction)? {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Nothing stored yet",
+            ));
+        }
+        // A task keeps its place in the list; a new one goes at the end
+        let position: i64 = transaction
+            .query_row(
+                "SELECT COALESCE(
+                    (SELECT position FROM tasks WHERE id = ?1),
+                    (SELECT COALESCE(MAX(position) + 1, 0) FROM tasks)
+                )",
+                [item.id.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(to_io)?;
+        let position = position as usize;
+        write_item(&transaction, item, position)?;
+        transaction.commit().map_err(to_io)?;
+        self.remember(item, position)
+    }
+
+    fn delete(&mut self, id: Uuid) -> io::Result<()> {
+        if !has_schema(&self.connection)? {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Nothing stored yet",
+            ));
+        }
+        self.connection
+            .execute("DELETE FROM tasks WHERE id = ?1", [id.to_string()])
+            .map_err(to_io)?;
+        self.written.remove(&id);
+        Ok(())
+    }
+
+    // SQLite lets several processes share the database, so there is no lock to take; changes by
+    // the others are picked up through `changed_externally`
+
+    fn changed_externally(&self) -> bool {
+        self.seen_version
+            .is_some_and(|seen| self.data_version() != Some(seen))
+    }
+
+    fn mark_seen(&mut self) {
+        self.seen_version = self.data_version();
+    }
+
+    fn backup(&mut self) -> io::Result<()> {
+        if !has_schema(&self.connection)? {
+            return Ok(());
+        }
+        self.file
+            .backup_with(false, |target| vacuum_into(&self.connection, target))
+    }
+
+    fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        self.file.list_backups()
+    }
+
+    fn list_names(&self) -> io::Result<Vec<String>> {
+        self.file.list_names()
+    }
+
+    fn open_list(&self, name: Option<&str>) -> io::Result<Box<dyn Storage>> {
+        Ok(Box::new(Self::open_file(self.file.list(name))?))
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        vec![self.file.path.clone()]
+    }
+
+    fn restore_backup(&mut self, chosen: &Backup) -> io::Result<Vec<TodoItem>> {
+        let source = Connection::open_with_flags(&chosen.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(to_io)?;
+        let items = read_items(&source)?;
+        self.file
+            .backup_with(true, |target| vacuum_into(&self.connection, target))?;
+        Ok(items)
+    }
+}
+
+// Writes a copy of the database to `target`
+fn vacuum_into(connection: &Connection, target: &Path) -> io::Result<()> {
+    connection
+        .execute("VACUUM INTO ?1", [target.to_string_lossy()])
+        .map(|_| ())
+        .map_err(to_io)
+}
+
+fn to_io(error: rusqlite::Error) -> io::Error {
+    io::Error::other(error)
+}
+
+fn has_schema(connection: &Connection) -> io::Result<bool> {
+    connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'tasks'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()
+        .map(|found| found.is_some())
+        .map_err(to_io)
+}
+
+fn schema_version(connection: &Connection) -> io::Result<i64> {
+    connection
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(to_io)
+}
+
+// Runs the upgrades a database of an older layout is missing. Newer databases are left alone, and
+// refused when read.
+fn upgrade_schema(connection: &mut Connection) -> io::Result<()> {
+    if !has_schema(connection)? {
+        return Ok(());
+    }
+    let version = schema_version(connection)?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+    let transaction = connection.transaction().map_err(to_io)?;
+    for upgrade in &UPGRADES[(version.max(1) - 1) as usize..] {
+        transaction.execute_batch(upgrade).map_err(to_io)?;
+    }
+    transaction
+        .pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(to_io)?;
+    transaction.commit().map_err(to_io)
+}
+
+fn create_schema(transaction: &Transaction) -> io::Result<()> {
+    transaction.execute_batch(SCHEMA).map_err(to_io)?;
+    transaction
+        .pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(to_io)
+}
+
+// Enums are stored by their variant name, e.g. "InProgress"
+fn enum_to_text<T: Serialize>(value: &T) -> io::Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(text) => Ok(text),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn enum_from_text<T: DeserializeOwned>(text: String) -> rusqlite::Result<T> {
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))
+}
+
+fn parse_id(text: &str) -> rusqlite::Result<Uuid> {
+    Uuid::parse_str(text).map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))
+}
+
+// Writes a task and replaces its tags, dependencies and time entries
+fn write_item(transaction: &Transaction, item: &TodoItem, position: usize) -> io::Result<()> {
+    let id = item.id.to_string();
+    let recurrence = item
+        .recurrence
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+    transaction
+        .execute(
+            "INSERT INTO tasks (id, position, title, info, status, priority, due_date, due_at,
+                scheduled, parent_id, collapsed, recurrence, estimate_minutes, created_at,
+                updated_at, completed_at, due_zone)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+            ON CONFLICT (id) DO UPDATE SET position = excluded.position,
+                title = excluded.title, info = excluded.info, status = excluded.status,
+                priority = excluded.priority, due_date = excluded.due_date,
+                due_at = excluded.due_at, scheduled = excluded.scheduled,
+                parent_id = excluded.parent_id, collapsed = excluded.collapsed,
+                recurrence = excluded.recurrence, estimate_minutes = excluded.estimate_minutes,
+                created_at = excluded.created_at, updated_at = excluded.updated_at,
+                completed_at = excluded.completed_at, due_zone = excluded.due_zone",
+            params![
+                id,
+                position as i64,
+                item.todo,
+                item.info,
+                enum_to_text(&item.status)?,
+                enum_to_text(&item.priority)?,
+                item.due_date,
+                item.due_at,
+                item.scheduled,
+                item.parent_id.map(|parent| parent.to_string()),
+                item.collapsed,
+                recurrence,
+                item.estimate_minutes,
+                item.created_at,
+                item.updated_at,
+                item.completed_at,
+                item.due_zone.map(|zone| zone.name()),
+            ],
+        )
+        .map_err(to_io)?;
+
+    for table in ["tags", "dependencies", "time_entries"] {
+        transaction
+            .execute(&format!("DELETE FROM {} WHERE task_id = ?1", table), [&id])
+            .map_err(to_io)?;
+    }
+    for (position, tag) in item.tags.iter().enumerate() {
+        transaction
+            .execute(
+                "INSERT INTO tags (task_id, position, tag) VALUES (?1, ?2, ?3)",
+                params![id, position as i64, tag],
+            )
+            .map_err(to_io)?;
+    }
+    for (position, prerequisite) in item.depends_on.iter().enumerate() {
+        transaction
+            .execute(
+                "INSERT INTO dependencies (task_id, position, depends_on) VALUES (?1, ?2, ?3)",
+                params![id, position as i64, prerequisite.to_string()],
+            )
+            .map_err(to_io)?;
+    }
+    for entry in &item.time_log {
+        transaction
+            .execute(
+                "INSERT INTO time_entries (task_id, start, end) VALUES (?1, ?2, ?3)",
+                params![id, entry.start, entry.end],
+            )
+            .map_err(to_io)?;
+    }
+    Ok(())
+}
+
+// Reads all tasks in list order. Fails with `NotFound` if nothing has been stored yet.
+fn read_items(connection: &Connection) -> io::Result<Vec<TodoItem>> {
+    if !has_schema(connection)? {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Nothing stored yet",
+        ));
+    }
+    let version = schema_version(connection)?;
+    if version > SCHEMA_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "The database uses schema version {}, but this version of TaskMaster only \
+                 understands up to version {}. Please upgrade TaskMaster to open it.",
+                version, SCHEMA_VERSION
+            ),
+        ));
+    }
+
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    let mut dependencies: HashMap<String, Vec<Uuid>> = HashMap::new();
+    let mut time_logs: HashMap<String, Vec<TimeEntry>> = HashMap::new();
+    let mut items = Vec::new();
+    let result: rusqlite::Result<()> = (|| {
+        let mut statement =
+            connection.prepare("SELECT task_id, tag FROM tags ORDER BY task_id, position")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            tags.entry(row.get(0)?).or_default().push(row.get(1)?);
+        }
+
+        let mut statement = connection
+            .prepare("SELECT task_id, depends_on FROM dependencies ORDER BY task_id, position")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let prerequisite: String = row.get(1)?;
+            dependencies
+                .entry(row.get(0)?)
+                .or_default()
+                .push(parse_id(&prerequisite)?);
+        }
+
+        let mut statement =
+            connection.prepare("SELECT task_id, start, end FROM time_entries ORDER BY start")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            time_logs.entry(row.get(0)?).or_default().push(TimeEntry {
+                start: row.get(1)?,
+                end: row.get(2)?,
+            });
+        }
+
+        let mut statement = connection.prepare(
+            "SELECT id, title, info, status, priority, due_date, due_at, scheduled, parent_id,
+                collapsed, recurrence, estimate_minutes, created_at, updated_at, completed_at,
+                due_zone
+            FROM tasks ORDER BY position",
+        )?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: String = row.get(0)?;
+            let parent_id: Option<String> = row.get(8)?;
+            let recurrence: Option<String> = row.get(10)?;
+            let due_zone: Option<String> = row.get(15)?;
+            let mut item = TodoItem::new(enum_from_text(row.get(3)?)?, "", "");
+            item.id = parse_id(&id)?;
+            item.todo = row.get(1)?;
+            item.info = row.get(2)?;
+            item.priority = enum_from_text(row.get(4)?)?;
+            item.due_date = row.get(5)?;
+            item.due_at = row.get(6)?;
+            item.due_zone = due_zone
+                .map(|zone| zone.parse::<Tz>())
+                .transpose()
+                .map_err(|error| rusqlite::Error::ToSqlConversionFailure(error.into()))?;
+            item.scheduled = row.get(7)?;
+            item.parent_id = parent_id.as_deref().map(parse_id).transpose()?;
+            item.collapsed = row.get(9)?;
+            item.recurrence = recurrence
+                .map(|rule| serde_json::from_str::<Recurrence>(&rule))
+                .transpose()
+                .map_err(|error| rusqlite::Error::ToSqlConversionFailure(Box::new(error)))?;
+            item.estimate_minutes = row.get(11)?;
+            item.created_at = row.get(12)?;
+            item.updated_at = row.get(13)?;
+            item.completed_at = row.get(14)?;
+            item.tags = tags.remove(&id).unwrap_or_default();
+            item.depends_on = dependencies.remove(&id).unwrap_or_default();
+            item.time_log = time_logs.remove(&id).unwrap_or_default();
+            items.push(item);
+        }
+        Ok(())
+    })();
+    result.map_err(to_io)?;
+    Ok(items)
+}
@@ -0,0 +1,95 @@
+// The task_file.rs file holds what the storages that keep each list in a file of its own have in
+// common: where the file is and where the other lists are kept, the lock against a second
+// TaskMaster, noticing outside edits, and backups.
+
+use crate::storage::backup::{self, Backup};
+use crate::storage::{file, lists, lock};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub struct TaskFile {
+    pub path: PathBuf,
+    // The main task file, which the other lists are kept next to
+    main: PathBuf,
+    lock: Option<File>,
+    // Modification time of the file when it was last read or written here
+    modified: Option<SystemTime>,
+}
+
+impl TaskFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            main: path.clone(),
+            path,
+            lock: None,
+            modified: None,
+        }
+    }
+
+    /// The file of the list called `name`, kept next to the main task file. `None` is the main
+    /// list.
+    pub fn list(&self, name: Option<&str>) -> Self {
+        Self {
+            main: self.main.clone(),
+            ..Self::new(lists::list_path(&self.main, name))
+        }
+    }
+
+    /// The names of the other lists, sorted.
+    pub fn list_names(&self) -> io::Result<Vec<String>> {
+        lists::list_names(&self.main)
+    }
+
+    /// Takes the lock on the file unless it's held already. Returns whether it is held.
+    pub fn lock(&mut self) -> io::Result<bool> {
+        if self.lock.is_none() {
+            self.lock = lock::try_lock(&self.path)?;
+        }
+        Ok(self.is_locked())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// Whether the file was written by someone else since `mark_seen`.
+    pub fn changed_externally(&self) -> bool {
+        let modified = file::modified_time(&self.path);
+        modified.is_some() && modified != self.modified
+    }
+
+    /// Notes the file as it is now, after it was read or written here.
+    pub fn mark_seen(&mut self) {
+        self.modified = file::modified_time(&self.path);
+    }
+
+    /// Backs up the file by copying it. See `backup_with`.
+    pub fn backup(&self, before_restore: bool) -> io::Result<()> {
+        backup::create_backup(&self.path, keep(before_restore)).map(|_| ())
+    }
+
+    /// Backs up the file, with `write` writing the backup to the path it is given. Before a
+    /// restore a backup is made even with backups turned off, so the restore can itself be undone.
+    pub fn backup_with(
+        &self,
+        before_restore: bool,
+        write: impl FnOnce(&Path) -> io::Result<()>,
+    ) -> io::Result<()> {
+        backup::create_backup_with(&self.path, keep(before_restore), write).map(|_| ())
+    }
+
+    pub fn list_backups(&self) -> io::Result<Vec<Backup>> {
+        backup::list_backups(&self.path)
+    }
+}
+
+// How many backups to keep
+fn keep(before_restore: bool) -> usize {
+    if before_restore {
+        backup::backup_count().max(1)
+    } else {
+        backup::backup_count()
+    }
+}
@@ -1,6 +1,8 @@
 // The tui.rs file is responsible for setting up and restoring the terminal state, as well as initializing error hooks for handling panics and errors gracefully.
 // It uses the ratatui crate for terminal UI management and color_eyre for enhanced error reporting.
 
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::{io, io::stdout};
 
 use color_eyre::config::HookBuilder;
@@ -28,6 +30,17 @@ pub fn init_error_hooks() -> color_eyre::Result<()> {
     Ok(())
 }
 
+/// Returns a flag that is raised when the process is told to stop (SIGTERM) or its terminal goes
+/// away (SIGHUP), so the main loop can save and exit in an orderly way instead of being killed.
+pub fn init_shutdown_signals() -> io::Result<Arc<AtomicBool>> {
+    let shutdown = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    for signal in [signal_hook::consts::SIGTERM, signal_hook::consts::SIGHUP] {
+        signal_hook::flag::register(signal, Arc::clone(&shutdown))?;
+    }
+    Ok(shutdown)
+}
+
 pub fn init_terminal() -> io::Result<Terminal<impl Backend>> {
     stdout().execute(EnterAlternateScreen)?;
     enable_raw_mode()?;
@@ -49,6 +62,17 @@ mod tests {
         assert!(result.is_ok(), "Failed to initialize error hooks");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_shutdown_signals_raise_the_flag() {
+        use std::sync::atomic::Ordering;
+
+        let shutdown = init_shutdown_signals().unwrap();
+        assert!(!shutdown.load(Ordering::Relaxed));
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        assert!(shutdown.load(Ordering::Relaxed));
+    }
+
     #[test]
     fn test_init_terminal() {
         if std::env::var("CI").is_ok() {
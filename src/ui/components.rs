@@ -1,32 +1,84 @@
-use crate::app::state::{EditingField, Mode};
+use crate::app::state::{Confirmation, EditingField, Mode};
 use crate::app::App;
 use crate::models;
+use crate::models::{dependencies, due, time_tracking};
+use crate::storage::lists;
 use crate::ui::theming::{
-    alternate_colors, COMPLETED_TEXT_FG_COLOR, NORMAL_ROW_BG, SELECTED_STYLE, TEXT_FG_COLOR,
-    TODO_HEADER_STYLE,
+    alternate_colors, priority_marker, status_marker, BLOCKED_TEXT_FG_COLOR, NORMAL_ROW_BG,
+    RUNNING_ROW_BG, SELECTED_STYLE, STATUS_MESSAGE_FG_COLOR, SUBTLE_TEXT_FG_COLOR, TEXT_FG_COLOR,
+    TIMER_FG_COLOR, TODO_HEADER_STYLE,
 };
+use chrono::{DateTime, Local, Utc};
 use ratatui::{
     buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
     text::{Line, Span, Text},
     widgets::{
-        Block, Borders, HighlightSpacing, List, ListItem, Padding, Paragraph, StatefulWidget,
-        Widget, Wrap,
+        Block, Borders, Clear, HighlightSpacing, List, ListItem, Padding, Paragraph,
+        StatefulWidget, Widget, Wrap,
     },
 };
 
+/// Asks for a passphrase before the app starts, e.g. to unlock an encrypted task file. What is typed
+/// is only shown as dots.
+pub struct PassphrasePrompt {
+    pub title: String,
+    pub question: String,
+    pub input: String,
+    // Why the last passphrase was not accepted, or that it is being checked
+    pub problem: Option<String>,
+}
+
+impl PassphrasePrompt {
+    pub fn new(title: &str, question: String) -> Self {
+        Self {
+            title: title.to_string(),
+            question,
+            input: String::new(),
+            problem: None,
+        }
+    }
+}
+
+impl Widget for &PassphrasePrompt {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Block::new()
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .render(area, buf);
+        let mut text = format!(
+            "{}\n\n> {}|",
+            self.question,
+            "•".repeat(self.input.chars().count())
+        );
+        if let Some(problem) = &self.problem {
+            text.push_str(&format!("\n\n{}", problem));
+        }
+        text.push_str("\n\n[Enter] continue   [Esc] quit");
+        render_popup(area, buf, &self.title, text);
+    }
+}
+
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(0), Constraint::Length(3)].as_ref())
+            .constraints(
+                [
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                    Constraint::Length(4),
+                ]
+                .as_ref(),
+            )
             .split(area);
 
+        self.render_header(layout[0], buf); // Which task file is open
+
         let content_layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(25), Constraint::Percentage(75)].as_ref())
-            .split(layout[0]);
+            .split(layout[1]);
 
         self.render_list(content_layout[0], buf); // Left pane for task list
 
@@ -34,51 +86,230 @@ impl Widget for &mut App {
             Mode::TaskList => self.render_selected_item(content_layout[1], buf), // Right pane for task details
             Mode::Editing => self.render_editing_item(content_layout[1], buf), // Right pane for editing
             Mode::Creating => self.render_editing_item(content_layout[1], buf), // Right pane for creating new task
+            Mode::Linking => self.render_selected_item(content_layout[1], buf), // Details of the prerequisite being picked
+            Mode::Confirming => {
+                self.render_selected_item(content_layout[1], buf);
+                self.render_confirmation(area, buf); // Popup on top of everything
+            }
+            Mode::Deferring => {
+                self.render_selected_item(content_layout[1], buf);
+                self.render_defer_prompt(area, buf);
+            }
+            Mode::TimeLog => self.render_time_log(content_layout[1], buf),
+            Mode::Backups => self.render_backups(content_layout[1], buf),
+            Mode::Lists => self.render_lists(content_layout[1], buf),
+            Mode::NamingList => {
+                self.render_lists(content_layout[1], buf);
+                self.render_list_name_prompt(area, buf);
+            }
+            Mode::AllLists => self.render_all_lists(content_layout[1], buf),
+            Mode::History => self.render_history(content_layout[1], buf),
+            Mode::HistoryTasks => self.render_history_tasks(content_layout[1], buf),
+            Mode::EditingTimeEntry => {
+                self.render_time_log(content_layout[1], buf);
+                self.render_time_entry_prompt(area, buf);
+            }
         }
 
-        App::render_footer(layout[1], buf); // Footer section at the bottom
+        self.render_footer(layout[2], buf); // Footer section at the bottom
     }
 }
 
 impl App {
-    fn render_footer(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Use ↓↑ to move, ← to unselect, → to change status, g/G to go top/bottom. Press 'e' to edit, 'q' to quit. Use Tab to switch fields, Ctrl+S to save.")
+    fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(Line::from(vec![
+            Span::styled(" TaskMaster  ", TODO_HEADER_STYLE),
+            Span::styled(
+                self.storage.describe(),
+                Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+            ),
+        ]))
+        .style(Style::default().bg(NORMAL_ROW_BG))
+        .render(area, buf);
+    }
+
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        if let Some(message) = &self.status_message {
+            Paragraph::new(message.as_str())
+                .style(Style::default().fg(STATUS_MESSAGE_FG_COLOR))
+                .centered()
+                .wrap(Wrap { trim: true })
+                .render(area, buf);
+            return;
+        }
+        if self.read_only {
+            let text = Text::from(vec![
+                Line::styled(
+                    "Read-only: changes are not saved. Press 'B' to restore a backup, or repair the task file and restart.",
+                    Style::default().fg(STATUS_MESSAGE_FG_COLOR),
+                ),
+                Line::raw(
+                    self.load_problem
+                        .as_deref()
+                        .and_then(|problem| problem.lines().next())
+                        .unwrap_or_default(),
+                ),
+            ]);
+            Paragraph::new(text)
+                .style(Style::default().fg(TEXT_FG_COLOR))
+                .centered()
+                .wrap(Wrap { trim: true })
+                .render(area, buf);
+            return;
+        }
+        let (estimated, count) = self.todo_list.estimate_summary();
+        let summary = match count {
+            0 => "No estimates for the open tasks in view".to_string(),
+            1 => format!(
+                "Estimated: {} for 1 open task in view",
+                time_tracking::format_minutes(estimated)
+            ),
+            n => format!(
+                "Estimated: {} for {} open tasks in view",
+                time_tracking::format_minutes(estimated),
+                n
+            ),
+        };
+        let help = match self.current_mode {
+            Mode::Linking => "Select the task it has to wait for and press Enter (again to remove the link). Esc to cancel.",
+            Mode::Backups => "Use ↓↑ to pick a backup and press Enter to restore it. Esc to go back.",
+            Mode::Lists if self.moving_task.is_some() => "Use ↓↑ to pick a list and press Enter to move the task there, or 'n' to move it to a new list. Esc to go back.",
+            Mode::Lists => "Use ↓↑ to pick a list and press Enter to switch to it. Press 'n' for a new list, 'a' for the open tasks of all lists, Esc to go back.",
+            Mode::AllLists => "Use ↓↑ to pick a task and press Enter to go to it in its list. Esc to go back.",
+            Mode::History => "Use ↓↑ to pick a commit and press Enter to see its tasks, or 'r' to restore all of them. Esc to go back.",
+            Mode::HistoryTasks => "Use ↓↑ to pick a task and press Enter to restore it, or 'r' to restore all tasks of this commit. Esc to go back.",
+            Mode::TimeLog => "Use ↓↑ to pick an interval. Press 'e' to edit it, 'a' to add one, 'x' to delete it, Esc to go back.",
+            _ => "Use ↓↑ to move, ←→ to collapse/expand, Space to complete, 't' to cycle status, 'x' to cancel, 'd' to add/remove a dependency, 'z' to defer, 'v' to show deferred, 'r' to start/stop the timer, 'L' for the time log, 'B' for backups, 'H' for the history, 'o' to switch lists, 'm' to move a task to another list, g/G to go top/bottom, 's' to sort by priority. Press 'n' for a new task, 'a' for a subtask, Tab/Shift+Tab to indent/outdent, 'e' to edit, 'q' to quit. Use Tab to switch fields, Ctrl+S to save.",
+        };
+        let saved = match &self.save_error {
+            Some(error) => Span::styled(
+                format!("Not saved: {}", error),
+                Style::default().fg(BLOCKED_TEXT_FG_COLOR),
+            ),
+            None if self.has_unsaved_changes() => Span::styled(
+                "Unsaved changes",
+                Style::default().fg(STATUS_MESSAGE_FG_COLOR),
+            ),
+            None => Span::styled("Saved", Style::default().fg(SUBTLE_TEXT_FG_COLOR)),
+        };
+        let text = Text::from(vec![
+            Line::from(vec![
+                Span::styled(summary, Style::default().fg(SUBTLE_TEXT_FG_COLOR)),
+                Span::styled("  ·  ", Style::default().fg(SUBTLE_TEXT_FG_COLOR)),
+                saved,
+            ]),
+            Line::raw(help),
+        ]);
+        Paragraph::new(text)
             .style(Style::default().fg(TEXT_FG_COLOR))
             .centered()
+            .wrap(Wrap { trim: true })
             .render(area, buf);
     }
 
     fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
+        let today = Local::now().date_naive();
+        let deferred = self
+            .todo_list
+            .items
+            .iter()
+            .filter(|item| item.is_deferred(today))
+            .count();
+        let list_name = lists::label(self.active_list.as_deref());
+        let title = match (deferred, self.todo_list.show_deferred) {
+            (0, _) => format!("TODO List: {}", list_name),
+            (n, false) => format!("TODO List: {} ({} deferred hidden)", list_name, n),
+            (n, true) => format!("TODO List: {} ({} deferred shown)", list_name, n),
+        };
         let block = Block::new()
-            .title(Line::raw("TODO List").centered())
+            .title(Line::raw(title).centered())
             .borders(Borders::ALL)
             .border_style(TODO_HEADER_STYLE)
             .style(Style::default().bg(NORMAL_ROW_BG));
 
-        // Iterate through all elements in the `items` and stylize them.
+        // Iterate through the visible rows of the task tree and stylize them.
+        let now = Local::now();
         let items: Vec<ListItem> = self
             .todo_list
-            .items
-            .iter()
+            .rows()
+            .into_iter()
             .enumerate()
-            .map(|(i, todo_item)| {
+            .map(|(i, row)| {
+                let todo_item = &self.todo_list.items[row.index];
                 let color = alternate_colors(i);
-                let status_symbol = if todo_item.status == models::Status::Completed {
-                    "✓"
-                } else {
-                    "☐"
+                let (status_symbol, status_color) = status_marker(todo_item.status);
+                let (priority_symbol, priority_color) = priority_marker(todo_item.priority);
+
+                let fold_symbol = match (row.has_children, todo_item.collapsed) {
+                    (false, _) => " ",
+                    (true, false) => "▾",
+                    (true, true) => "▸",
                 };
 
-                let content = Line::styled(
-                    format!("{} {}", status_symbol, todo_item.todo),
-                    Style::default().fg(if todo_item.status == models::Status::Completed {
-                        COMPLETED_TEXT_FG_COLOR // Color for completed tasks
-                    } else {
-                        TEXT_FG_COLOR
-                    }),
-                );
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{} ", priority_symbol),
+                        Style::default().fg(priority_color),
+                    ),
+                    Span::raw(format!(
+                        "{}{} {} {}",
+                        "  ".repeat(row.depth),
+                        fold_symbol,
+                        status_symbol,
+                        todo_item.todo
+                    )),
+                ];
+                if let Some(due) = todo_item.due().filter(|_| !todo_item.status.is_closed()) {
+                    spans.push(Span::styled(
+                        format!(" {}", due::remaining(due, now)),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ));
+                }
+                if let Some(scheduled) =
+                    todo_item.scheduled.filter(|_| todo_item.is_deferred(today))
+                {
+                    spans.push(Span::styled(
+                        format!(" ⏸ {}", scheduled),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ));
+                }
+                if todo_item.recurrence.is_some() {
+                    spans.push(Span::styled(
+                        " ↻",
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ));
+                }
+                if !dependencies::open_blockers(&self.todo_list.items, todo_item).is_empty() {
+                    spans.push(Span::styled(
+                        " [blocked]",
+                        Style::default().fg(BLOCKED_TEXT_FG_COLOR),
+                    ));
+                }
+                if row.has_children {
+                    let (done, total) =
+                        models::tree::child_progress(&self.todo_list.items, todo_item.id);
+                    spans.push(Span::styled(
+                        format!(" ({}/{} done)", done, total),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ));
+                }
+
+                let running = todo_item.is_timer_running();
+                if running {
+                    let elapsed = time_tracking::total(&todo_item.time_log, Utc::now());
+                    spans.push(Span::styled(
+                        format!(" ⏱ {}", time_tracking::format_clock(elapsed)),
+                        Style::default().fg(TIMER_FG_COLOR),
+                    ));
+                }
 
-                ListItem::new(content).style(Style::default().bg(color))
+                let content = Line::from(spans).style(Style::default().fg(status_color));
+
+                ListItem::new(content).style(Style::default().bg(if running {
+                    RUNNING_ROW_BG
+                } else {
+                    color
+                }))
             })
             .collect();
 
@@ -101,24 +332,55 @@ impl App {
             .style(Style::default().bg(NORMAL_ROW_BG))
             .padding(Padding::horizontal(1));
 
-        let info = if let Some(i) = self.todo_list.state.selected() {
-            let selected_task = &self.todo_list.items[i];
+        let info = if let Some(selected_task) = self.todo_list.selected() {
+            let now = Local::now();
+            let mut details = vec![
+                selected_task.due().map_or("No due date".to_string(), |d| {
+                    format!("Due: {} ({})", d, due::remaining(d, now))
+                }),
+                selected_task
+                    .scheduled
+                    .map_or("Not scheduled".to_string(), |d| format!("Scheduled: {}", d)),
+                format!("Priority: {}", selected_task.priority.label()),
+            ];
+            if let Some(estimate) = selected_task.estimate_minutes {
+                let tracked = time_tracking::total(&selected_task.time_log, Utc::now());
+                details.push(format!(
+                    "Estimate: {} ({})",
+                    time_tracking::format_minutes(estimate),
+                    time_tracking::compare_to_estimate(estimate, tracked)
+                ));
+            }
+            if let Some(recurrence) = &selected_task.recurrence {
+                details.push(format!("Repeats: {}", recurrence.describe()));
+            }
+            if !selected_task.time_log.is_empty() {
+                let tracked = time_tracking::total(&selected_task.time_log, Utc::now());
+                details.push(format!(
+                    "Tracked: {}{}",
+                    time_tracking::format_clock(tracked),
+                    if selected_task.is_timer_running() {
+                        " (running)"
+                    } else {
+                        ""
+                    }
+                ));
+            }
+            details.push(if !selected_task.tags.is_empty() {
+                format!("Tags: {}", selected_task.tags.join(", "))
+            } else {
+                "No tags".to_string()
+            });
             format!(
-                "{}\n\nDescription:\n{}\n\n{}\n{}",
-                if selected_task.status == models::Status::Completed {
-                    format!("✓ DONE: {}", selected_task.todo)
-                } else {
-                    format!("☐ TODO: {}", selected_task.todo)
-                },
+                "{} {}: {}\n\nDescription:\n{}\n\n{}{}{}\n\n{}",
+                status_marker(selected_task.status).0,
+                selected_task.status.label(),
+                selected_task.todo,
                 selected_task.info,
-                selected_task
-                    .due_date
-                    .map_or("No due date".to_string(), |d| format!("Due: {}", d)),
-                if !selected_task.tags.is_empty() {
-                    format!("Tags: {}", selected_task.tags.join(", "))
-                } else {
-                    "No tags".to_string()
-                }
+                details.join("\n"),
+                self.format_hierarchy(selected_task),
+                self.format_dependencies(selected_task),
+                App::format_timestamps(selected_task)
             )
         } else {
             "No task selected...".to_string()
@@ -131,6 +393,383 @@ impl App {
             .render(area, buf);
     }
 
+    // Parent and subtask progress lines, empty for tasks outside any hierarchy
+    fn format_hierarchy(&self, task: &models::TodoItem) -> String {
+        let mut lines = String::new();
+        if let Some(parent) = task.parent_id.and_then(|id| self.todo_list.get(id)) {
+            lines.push_str(&format!("\nSubtask of: {}", parent.todo));
+        }
+        let (done, total) = models::tree::child_progress(&self.todo_list.items, task.id);
+        if total > 0 {
+            lines.push_str(&format!("\nSubtasks: {}/{} done", done, total));
+        }
+        lines
+    }
+
+    // What the task is blocked by and what it blocks, empty for tasks without dependencies
+    fn format_dependencies(&self, task: &models::TodoItem) -> String {
+        let items = &self.todo_list.items;
+        let mut lines = String::new();
+        let blocked_by: Vec<String> = items
+            .iter()
+            .filter(|item| task.depends_on.contains(&item.id))
+            .map(|item| format!("  {} {}", status_marker(item.status).0, item.todo))
+            .collect();
+        if !blocked_by.is_empty() {
+            lines.push_str(&format!("\n\nBlocked by:\n{}", blocked_by.join("\n")));
+        }
+        let blocks: Vec<String> = dependencies::dependents(items, task.id)
+            .into_iter()
+            .map(|item| format!("  {} {}", status_marker(item.status).0, item.todo))
+            .collect();
+        if !blocks.is_empty() {
+            lines.push_str(&format!("\n\nBlocks:\n{}", blocks.join("\n")));
+        }
+        lines
+    }
+
+    fn render_confirmation(&self, area: Rect, buf: &mut Buffer) {
+        let question = match self.pending_confirmation {
+            Some(Confirmation::CompleteSubtasks(id)) => format!(
+                "\"{}\" still has open subtasks.\n\nComplete them too?\n\n[y] complete all   [n] only this task   [Esc] cancel",
+                self.todo_list.get(id).map_or("", |task| task.todo.as_str())
+            ),
+            Some(Confirmation::RestoreBackup(i)) => format!(
+                "Replace all tasks with the backup from {}?\n\nThe current tasks are backed up first.\n\n[y] restore   [n/Esc] cancel",
+                self.backups
+                    .get(i)
+                    .map_or(String::new(), |backup| backup
+                        .created
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string())
+            ),
+            Some(Confirmation::RestoreRevision(i)) => format!(
+                "Replace all tasks with those from {}?\n\nThe current tasks stay in the history.\n\n[y] restore   [n/Esc] cancel",
+                self.history.get(i).map_or(String::new(), |revision| format!(
+                    "{} ({})",
+                    revision.committed.format("%Y-%m-%d %H:%M:%S"),
+                    revision.message
+                ))
+            ),
+            Some(Confirmation::UseSalvagedTasks) => format!(
+                "{}\n\n[y] continue with the salvaged tasks   [n/Esc] browse read-only",
+                self.load_problem.as_deref().unwrap_or_default()
+            ),
+            Some(Confirmation::ReadOnly) => format!(
+                "{}\n\nThe app is read-only; changes will not be saved.\n\n[Esc] continue",
+                self.load_problem.as_deref().unwrap_or_default()
+            ),
+            Some(Confirmation::RestoreDump) => format!(
+                "TaskMaster crashed at {} with changes that were not saved yet.\n\nRestore the tasks as they were then? They replace the tasks of {}.\n\n[y] restore   [n] throw them away   [Esc] decide next time",
+                self.emergency_dump.as_ref().map_or(String::new(), |(_, dump)| dump
+                    .written_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()),
+                lists::label(
+                    self.emergency_dump
+                        .as_ref()
+                        .and_then(|(_, dump)| dump.list.as_deref())
+                )
+            ),
+            Some(Confirmation::ExternalChange) => "The task file was changed outside this window.\n\n[r] reload it, dropping changes made here\n[m] merge both\n[k/Esc] keep these and overwrite it".to_string(),
+            None => return,
+        };
+
+        render_popup(area, buf, "Confirm", question);
+    }
+
+    fn render_time_log(&mut self, area: Rect, buf: &mut Buffer) {
+        let Some(task) = self.todo_list.selected() else {
+            return;
+        };
+        let now = Utc::now();
+        let block = Block::new()
+            .title(
+                Line::raw(format!(
+                    "Time Log: {} ({})",
+                    task.todo,
+                    time_tracking::format_clock(time_tracking::total(&task.time_log, now))
+                ))
+                .centered(),
+            )
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let entries: Vec<ListItem> = task
+            .time_log
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                ListItem::new(format!(
+                    "{}  ({})",
+                    entry.describe(),
+                    time_tracking::format_clock(entry.duration(now))
+                ))
+                .style(Style::default().fg(TEXT_FG_COLOR).bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.time_log_state);
+    }
+
+    fn render_backups(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new()
+            .title(Line::raw("Backups (newest first)").centered())
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let entries: Vec<ListItem> = self
+            .backups
+            .iter()
+            .enumerate()
+            .map(|(i, backup)| {
+                ListItem::new(format!(
+                    "{}  ({})",
+                    backup.created.format("%Y-%m-%d %H:%M:%S"),
+                    backup.path.display()
+                ))
+                .style(Style::default().fg(TEXT_FG_COLOR).bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.backup_state);
+    }
+
+    fn render_lists(&mut self, area: Rect, buf: &mut Buffer) {
+        let title = match self.moving_task.and_then(|id| self.todo_list.get(id)) {
+            Some(task) => format!("Move \"{}\" to", task.todo),
+            None => "Lists".to_string(),
+        };
+        let block = Block::new()
+            .title(Line::raw(title).centered())
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let entries: Vec<ListItem> = self
+            .list_choices
+            .iter()
+            .enumerate()
+            .map(|(i, list)| {
+                let label = lists::label(list.as_deref());
+                let text = if *list == self.active_list {
+                    format!("{}  (current)", label)
+                } else {
+                    label.to_string()
+                };
+                ListItem::new(text)
+                    .style(Style::default().fg(TEXT_FG_COLOR).bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+
+    fn render_list_name_prompt(&self, area: Rect, buf: &mut Buffer) {
+        let cursor = if self.cursor_visible { "|" } else { " " };
+        let text = format!(
+            "Name of the new list\nLetters, digits, spaces, '-' and '_'\n\n> {}{}\n\n[Enter] create   [Esc] cancel",
+            self.prompt_input, cursor
+        );
+        render_popup(area, buf, "New List", text);
+    }
+
+    fn render_history(&mut self, area: Rect, buf: &mut Buffer) {
+        let title = format!(
+            "History of {} (newest first)",
+            lists::label(self.active_list.as_deref())
+        );
+        let block = Block::new()
+            .title(Line::raw(title).centered())
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let entries: Vec<ListItem> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(i, revision)| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{} {}  ",
+                            revision.committed.format("%Y-%m-%d %H:%M"),
+                            revision.short_id()
+                        ),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ),
+                    Span::styled(revision.message.clone(), Style::default().fg(TEXT_FG_COLOR)),
+                ]))
+                .style(Style::default().bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.history_state);
+    }
+
+    fn render_history_tasks(&mut self, area: Rect, buf: &mut Buffer) {
+        let title = match self.selected_revision_label() {
+            Some(label) => format!("Tasks as of {}", label),
+            None => "Tasks".to_string(),
+        };
+        let block = Block::new()
+            .title(Line::raw(title).centered())
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let entries: Vec<ListItem> = self
+            .history_tasks
+            .iter()
+            .enumerate()
+            .map(|(i, task)| {
+                let (status_symbol, status_color) = status_marker(task.status);
+                // Tasks that are gone or different now are the ones worth restoring
+                let note = match self.todo_list.get(task.id) {
+                    None => "  (deleted since)",
+                    Some(current) if current != task => "  (changed since)",
+                    Some(_) => "",
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{} {}", status_symbol, task.todo),
+                        Style::default().fg(status_color),
+                    ),
+                    Span::styled(note, Style::default().fg(SUBTLE_TEXT_FG_COLOR)),
+                ]))
+                .style(Style::default().bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.history_task_state);
+    }
+
+    // When the commit picked from the history was made, with its message
+    fn selected_revision_label(&self) -> Option<String> {
+        let i = self.history_state.selected()?;
+        let revision = self
+            .history
+            .get(i.min(self.history.len().saturating_sub(1)))?;
+        Some(format!(
+            "{} ({})",
+            revision.committed.format("%Y-%m-%d %H:%M"),
+            revision.message
+        ))
+    }
+
+    fn render_all_lists(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new()
+            .title(Line::raw("Open tasks in all lists").centered())
+            .borders(Borders::ALL)
+            .border_style(TODO_HEADER_STYLE)
+            .style(Style::default().bg(NORMAL_ROW_BG))
+            .padding(Padding::horizontal(1));
+
+        let now = Local::now();
+        let entries: Vec<ListItem> = self
+            .all_tasks
+            .iter()
+            .enumerate()
+            .map(|(i, (list, task))| {
+                let (status_symbol, status_color) = status_marker(task.status);
+                let (priority_symbol, priority_color) = priority_marker(task.priority);
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{} ", priority_symbol),
+                        Style::default().fg(priority_color),
+                    ),
+                    Span::styled(
+                        format!("[{}] ", lists::label(list.as_deref())),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ),
+                    Span::styled(
+                        format!("{} {}", status_symbol, task.todo),
+                        Style::default().fg(status_color),
+                    ),
+                ];
+                if let Some(due) = task.due() {
+                    spans.push(Span::styled(
+                        format!(" {}", due::remaining(due, now)),
+                        Style::default().fg(SUBTLE_TEXT_FG_COLOR),
+                    ));
+                }
+                ListItem::new(Line::from(spans)).style(Style::default().bg(alternate_colors(i)))
+            })
+            .collect();
+
+        let list = List::new(entries)
+            .block(block)
+            .highlight_style(SELECTED_STYLE)
+            .highlight_symbol(">")
+            .highlight_spacing(HighlightSpacing::Always);
+        StatefulWidget::render(list, area, buf, &mut self.all_tasks_state);
+    }
+
+    fn render_time_entry_prompt(&self, area: Rect, buf: &mut Buffer) {
+        let cursor = if self.cursor_visible { "|" } else { " " };
+        let text = format!(
+            "Start and end of the interval, e.g. 2026-10-18 09:00 - 10:30\nEnd with 'running' to keep the timer going\n\n> {}{}\n\n[Enter] save   [Esc] cancel",
+            self.prompt_input, cursor
+        );
+        render_popup(area, buf, "Time Entry", text);
+    }
+
+    fn render_defer_prompt(&self, area: Rect, buf: &mut Buffer) {
+        let cursor = if self.cursor_visible { "|" } else { " " };
+        let text = format!(
+            "Defer for how long?\n3d, 2w, 1m, a date like 2026-11-02, or 'now' to undo\n\n> {}{}\n\n[Enter] defer   [Esc] cancel",
+            self.prompt_input, cursor
+        );
+        render_popup(area, buf, "Defer", text);
+    }
+
+    fn format_timestamps(task: &models::TodoItem) -> String {
+        let format =
+            |t: DateTime<Utc>| t.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+        let mut lines = vec![
+            format!("Created: {}", format(task.created_at)),
+            format!("Updated: {}", format(task.updated_at)),
+        ];
+        if let Some(completed_at) = task.completed_at {
+            lines.push(format!("Completed: {}", format(completed_at)));
+        }
+        lines.join("\n")
+    }
+
     fn render_editing_item(&self, area: Rect, buf: &mut Buffer) {
         if let Some(editing_task) = &self.editing_task {
             let block = Block::new()
@@ -177,7 +816,7 @@ impl App {
             let due_date_line = if self.current_editing_field == EditingField::DueDate {
                 let cursor = if self.cursor_visible { "|" } else { " " };
                 Line::from(vec![
-                    Span::raw("> Due Date: "),
+                    Span::raw("> Due Date (e.g. tomorrow 14:00): "),
                     Span::raw(due_date_text),
                     Span::styled(cursor, cursor_style),
                 ])
@@ -185,6 +824,47 @@ impl App {
                 Line::from(vec![Span::raw("Due Date: "), Span::raw(due_date_text)])
             };
 
+            // Estimate field with cursor if active
+            let estimate_text = editing_task.estimate_temp.clone().unwrap_or_default();
+            let estimate_line = if self.current_editing_field == EditingField::Estimate {
+                let cursor = if self.cursor_visible { "|" } else { " " };
+                Line::from(vec![
+                    Span::raw("> Estimate (e.g. 30m, 2h, 1d): "),
+                    Span::raw(estimate_text),
+                    Span::styled(cursor, cursor_style),
+                ])
+            } else {
+                Line::from(vec![Span::raw("Estimate: "), Span::raw(estimate_text)])
+            };
+
+            // Recurrence field with cursor if active
+            let recurrence_text = editing_task.recurrence_temp.clone().unwrap_or_default();
+            let recurrence_line = if self.current_editing_field == EditingField::Recurrence {
+                let cursor = if self.cursor_visible { "|" } else { " " };
+                Line::from(vec![
+                    Span::raw("> Repeat (e.g. weekly on mon,thu): "),
+                    Span::raw(recurrence_text),
+                    Span::styled(cursor, cursor_style),
+                ])
+            } else {
+                Line::from(vec![Span::raw("Repeat: "), Span::raw(recurrence_text)])
+            };
+
+            // Priority field with cursor if active
+            let priority_line = if self.current_editing_field == EditingField::Priority {
+                let cursor = if self.cursor_visible { "|" } else { " " };
+                Line::from(vec![
+                    Span::raw("> Priority (0-4, +/-): "),
+                    Span::raw(editing_task.priority.label()),
+                    Span::styled(cursor, cursor_style),
+                ])
+            } else {
+                Line::from(vec![
+                    Span::raw("Priority: "),
+                    Span::raw(editing_task.priority.label()),
+                ])
+            };
+
             // Tags field with cursor if active
             let tags_line = if self.current_editing_field == EditingField::Tags {
                 let cursor = if self.cursor_visible { "|" } else { " " };
@@ -205,6 +885,9 @@ impl App {
                 task_name_line,
                 description_line,
                 due_date_line,
+                estimate_line,
+                recurrence_line,
+                priority_line,
                 tags_line,
             ]);
 
@@ -216,3 +899,40 @@ impl App {
         }
     }
 }
+
+/// A rectangle of the given width percentage and height, centered within `area`.
+fn centered_rect(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+/// Draws a bordered popup with the given text over the middle of `area`.
+fn render_popup(area: Rect, buf: &mut Buffer, title: &str, text: String) {
+    // Height of the text once wrapped to the popup, plus the borders
+    let inner_width = usize::from((area.width * 60 / 100).saturating_sub(4)).max(1);
+    let height: usize = text
+        .lines()
+        .map(|line| line.chars().count().div_ceil(inner_width).max(1))
+        .sum();
+    let popup = centered_rect(area, 60, height as u16 + 2);
+    Clear.render(popup, buf);
+    Paragraph::new(text)
+        .block(
+            Block::new()
+                .title(Line::raw(title).centered())
+                .borders(Borders::ALL)
+                .border_style(TODO_HEADER_STYLE)
+                .style(Style::default().bg(NORMAL_ROW_BG))
+                .padding(Padding::horizontal(1)),
+        )
+        .style(Style::default().fg(TEXT_FG_COLOR))
+        .centered()
+        .wrap(Wrap { trim: false })
+        .render(popup, buf);
+}
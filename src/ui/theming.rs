@@ -1,3 +1,4 @@
+use crate::models::{Priority, Status};
 use ratatui::style::{Color, Modifier, Style};
 
 pub const TODO_HEADER_STYLE: Style = Style::new()
@@ -7,6 +8,7 @@ pub const TODO_HEADER_STYLE: Style = Style::new()
 
 pub const NORMAL_ROW_BG: Color = Color::Rgb(45, 45, 45); // Dark, muted gray for row background
 pub const ALT_ROW_BG_COLOR: Color = Color::Rgb(50, 50, 50); // Slightly lighter gray for alternate rows
+pub const RUNNING_ROW_BG: Color = Color::Rgb(40, 60, 50); // Dark green tint for the task with a running timer
 
 pub const SELECTED_STYLE: Style = Style::new()
     .fg(Color::Rgb(255, 255, 255)) // Brighter white for selected text
@@ -15,12 +17,48 @@ pub const SELECTED_STYLE: Style = Style::new()
     .add_modifier(Modifier::ITALIC); // Bold and italic for emphasis without overpowering
 
 pub const TEXT_FG_COLOR: Color = Color::Rgb(220, 220, 220); // Soft light gray for regular text
+pub const SUBTLE_TEXT_FG_COLOR: Color = Color::Rgb(150, 150, 150); // Mid gray for secondary details
+pub const TIMER_FG_COLOR: Color = Color::Rgb(120, 220, 170); // Mint green for the running timer
+pub const STATUS_MESSAGE_FG_COLOR: Color = Color::Rgb(250, 215, 120); // Warm yellow for footer feedback
 pub const COMPLETED_TEXT_FG_COLOR: Color = Color::Rgb(144, 238, 144); // Subtle, light green for completed tasks
 
+pub const IN_PROGRESS_TEXT_FG_COLOR: Color = Color::Rgb(130, 200, 255); // Light blue for work in progress
+pub const BLOCKED_TEXT_FG_COLOR: Color = Color::Rgb(240, 120, 120); // Muted red for blocked tasks
+pub const WAITING_TEXT_FG_COLOR: Color = Color::Rgb(220, 190, 120); // Sand for tasks waiting on others
+pub const CANCELLED_TEXT_FG_COLOR: Color = Color::Rgb(130, 130, 130); // Dim gray for cancelled tasks
+
+pub const PRIORITY_LOW_FG_COLOR: Color = Color::Rgb(135, 175, 215); // Calm blue for low priority
+pub const PRIORITY_MEDIUM_FG_COLOR: Color = Color::Rgb(240, 200, 100); // Amber for medium priority
+pub const PRIORITY_HIGH_FG_COLOR: Color = Color::Rgb(255, 150, 80); // Orange for high priority
+pub const PRIORITY_URGENT_FG_COLOR: Color = Color::Rgb(255, 85, 85); // Strong red for urgent tasks
+
 pub const fn alternate_colors(i: usize) -> Color {
-    if i % 2 == 0 {
+    if i.is_multiple_of(2) {
         NORMAL_ROW_BG
     } else {
         ALT_ROW_BG_COLOR
     }
 }
+
+/// Marker and colour shown in front of a task for its priority.
+pub const fn priority_marker(priority: Priority) -> (&'static str, Color) {
+    match priority {
+        Priority::None => (" ", TEXT_FG_COLOR),
+        Priority::Low => ("·", PRIORITY_LOW_FG_COLOR),
+        Priority::Medium => ("!", PRIORITY_MEDIUM_FG_COLOR),
+        Priority::High => ("‼", PRIORITY_HIGH_FG_COLOR),
+        Priority::Urgent => ("⚑", PRIORITY_URGENT_FG_COLOR),
+    }
+}
+
+/// Symbol and text colour used for a task in the given status.
+pub const fn status_marker(status: Status) -> (&'static str, Color) {
+    match status {
+        Status::Todo => ("☐", TEXT_FG_COLOR),
+        Status::InProgress => ("◐", IN_PROGRESS_TEXT_FG_COLOR),
+        Status::Blocked => ("⊘", BLOCKED_TEXT_FG_COLOR),
+        Status::Waiting => ("⧗", WAITING_TEXT_FG_COLOR),
+        Status::Completed => ("✓", COMPLETED_TEXT_FG_COLOR),
+        Status::Cancelled => ("✗", CANCELLED_TEXT_FG_COLOR),
+    }
+}